@@ -1,3 +1,43 @@
 fn main() {
-    tauri_build::build()
+    // Every command gets `allow-*`/`deny-*` permissions; only the ones granted
+    // in `capabilities/` can be invoked from the webview
+    tauri_build::try_build(
+        tauri_build::Attributes::new().app_manifest(tauri_build::AppManifest::new().commands(&[
+            "list_apps",
+            "list_running_apps",
+            "search_apps",
+            "get_frequent_apps",
+            "get_favorites",
+            "add_favorite",
+            "remove_favorite",
+            "reorder_favorites",
+            "open_app",
+            "open_with",
+            "quit_app",
+            "force_quit_app",
+            "track_app_usage",
+            "set_usage_tracking_paused",
+            "set_usage_excluded",
+            "forget_app_usage",
+            "purge_usage_history",
+            "get_usage_stats",
+            "export_usage_stats",
+            "export_state",
+            "import_state",
+            "get_unused_apps",
+            "trash_unused_apps",
+            "trash_app",
+            "restore_trashed_app",
+            "get_trash_history",
+            "show_main_window",
+            "hide_main_window",
+            "set_hotkey",
+            "get_settings",
+            "get_settings_error",
+            "update_settings",
+            "get_app_icon_base64",
+            "read_icon_file",
+        ])),
+    )
+    .expect("failed to run tauri-build");
 }
@@ -0,0 +1,422 @@
+use crate::discovery::AppInfo;
+use crate::settings::Settings;
+use crate::usage::{self, AppUsage};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Bumped whenever the archive layout changes in a way older builds can't
+/// read. Archives from newer builds are refused rather than half-imported.
+pub const ARCHIVE_VERSION: u32 = 1;
+
+/// Everything the launcher persists, in one document for moving to another
+/// machine. The index snapshot is left out since it is rebuilt by a rescan.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StateArchive {
+    pub version: u32,
+    pub exported_at: String,
+    /// Version of the build that wrote the archive, for troubleshooting
+    pub app_version: String,
+    pub settings: Settings,
+    /// Pinned apps in their arranged order
+    pub favorites: Vec<String>,
+    pub usage: Vec<AppUsage>,
+}
+
+impl StateArchive {
+    pub fn new(settings: Settings, favorites: Vec<String>, usage: Vec<AppUsage>) -> Self {
+        StateArchive {
+            version: ARCHIVE_VERSION,
+            exported_at: chrono::Utc::now().to_rfc3339(),
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            settings,
+            favorites,
+            usage,
+        }
+    }
+
+    /// Reads an archive, checking its version before anything else so a
+    /// newer layout is reported as such instead of as a parse error
+    pub fn parse(json: &str) -> Result<Self, String> {
+        let value: Value =
+            serde_json::from_str(json).map_err(|e| format!("Failed to parse archive: {}", e))?;
+        let version = value
+            .get("version")
+            .and_then(Value::as_u64)
+            .ok_or("Archive has no version")?;
+        if version == 0 || version > u64::from(ARCHIVE_VERSION) {
+            return Err(format!(
+                "Archive version {} is not supported; this build reads up to version {}",
+                version, ARCHIVE_VERSION
+            ));
+        }
+
+        let archive: StateArchive =
+            serde_json::from_value(value).map_err(|e| format!("Failed to parse archive: {}", e))?;
+        archive.settings.validate()?;
+        Ok(archive)
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportMode {
+    /// The archive overwrites every store
+    Replace,
+    /// Launch counts are summed and favorites and usage exclusions unioned;
+    /// other settings stay as they are
+    Merge,
+}
+
+/// What an import changes, or would change for a dry run
+#[derive(Serialize, Debug, Clone)]
+pub struct ImportDiff {
+    pub mode: ImportMode,
+    pub dry_run: bool,
+    /// Top-level settings whose value changes
+    pub settings_changed: Vec<String>,
+    pub favorites_added: Vec<String>,
+    pub favorites_removed: Vec<String>,
+    /// Whether the favorites end up in a different order
+    pub favorites_reordered: bool,
+    pub usage_added: Vec<String>,
+    pub usage_updated: Vec<String>,
+    pub usage_removed: Vec<String>,
+    pub launches_before: u64,
+    pub launches_after: u64,
+}
+
+impl ImportDiff {
+    pub fn favorites_changed(&self) -> bool {
+        !self.favorites_added.is_empty()
+            || !self.favorites_removed.is_empty()
+            || self.favorites_reordered
+    }
+
+    pub fn usage_changed(&self) -> bool {
+        !self.usage_added.is_empty()
+            || !self.usage_updated.is_empty()
+            || !self.usage_removed.is_empty()
+    }
+}
+
+/// The stores as they would be after importing `archive` into `current`.
+/// `usage_file` always keeps its current value: it names a path on this
+/// machine, and the imported history is written there. Usage of apps
+/// excluded from tracking in the resulting settings is left out, and so are
+/// imported favorites that aren't among the indexed `apps`.
+pub fn plan_import(
+    current: &StateArchive,
+    mut archive: StateArchive,
+    mode: ImportMode,
+    apps: &[AppInfo],
+) -> StateArchive {
+    archive
+        .favorites
+        .retain(|bundle_id| apps.iter().any(|app| app.bundle_id == *bundle_id));
+
+    let mut planned = match mode {
+        ImportMode::Replace => {
+            let mut settings = archive.settings;
+            settings.usage_file = current.settings.usage_file.clone();
+            StateArchive {
+                settings,
+                favorites: archive.favorites,
+                usage: archive.usage,
+                ..current.clone()
+            }
+        }
+        ImportMode::Merge => {
+            let mut settings = current.settings.clone();
+            for bundle_id in archive.settings.usage_tracking.excluded {
+                if !settings.usage_tracking.excluded.contains(&bundle_id) {
+                    settings.usage_tracking.excluded.push(bundle_id);
+                }
+            }
+
+            let mut favorites = current.favorites.clone();
+            for bundle_id in archive.favorites {
+                if !favorites.contains(&bundle_id) {
+                    favorites.push(bundle_id);
+                }
+            }
+
+            let mut usage_data = current.usage.clone();
+            usage::merge(&mut usage_data, &archive.usage);
+
+            StateArchive {
+                settings,
+                favorites,
+                usage: usage_data,
+                ..current.clone()
+            }
+        }
+    };
+
+    // History of excluded apps is never written, imported or not
+    let tracking = planned.settings.usage_tracking.clone();
+    planned
+        .usage
+        .retain(|usage| !tracking.is_excluded(&usage.bundle_id));
+    planned
+}
+
+pub fn diff(
+    current: &StateArchive,
+    planned: &StateArchive,
+    mode: ImportMode,
+    dry_run: bool,
+) -> ImportDiff {
+    let settings_changed = match (
+        serde_json::to_value(&current.settings),
+        serde_json::to_value(&planned.settings),
+    ) {
+        (Ok(Value::Object(before)), Ok(Value::Object(after))) => after
+            .iter()
+            .filter(|(key, value)| before.get(*key) != Some(*value))
+            .map(|(key, _)| key.clone())
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    let favorites_added: Vec<String> = planned
+        .favorites
+        .iter()
+        .filter(|id| !current.favorites.contains(id))
+        .cloned()
+        .collect();
+    let favorites_removed: Vec<String> = current
+        .favorites
+        .iter()
+        .filter(|id| !planned.favorites.contains(id))
+        .cloned()
+        .collect();
+    let kept: Vec<&String> = planned
+        .favorites
+        .iter()
+        .filter(|id| current.favorites.contains(id))
+        .collect();
+    let favorites_reordered = current
+        .favorites
+        .iter()
+        .filter(|id| planned.favorites.contains(id))
+        .ne(kept);
+
+    let find = |usage_data: &[AppUsage], bundle_id: &str| {
+        usage_data
+            .iter()
+            .find(|usage| usage.bundle_id == bundle_id)
+            .cloned()
+    };
+    let mut usage_added = Vec::new();
+    let mut usage_updated = Vec::new();
+    for usage in &planned.usage {
+        match find(&current.usage, &usage.bundle_id) {
+            None => usage_added.push(usage.bundle_id.clone()),
+            Some(existing) if existing != *usage => usage_updated.push(usage.bundle_id.clone()),
+            Some(_) => {}
+        }
+    }
+    let usage_removed = current
+        .usage
+        .iter()
+        .filter(|usage| find(&planned.usage, &usage.bundle_id).is_none())
+        .map(|usage| usage.bundle_id.clone())
+        .collect();
+
+    let launches = |usage_data: &[AppUsage]| {
+        usage_data
+            .iter()
+            .map(|usage| u64::from(usage.launch_count))
+            .sum()
+    };
+
+    ImportDiff {
+        mode,
+        dry_run,
+        settings_changed,
+        favorites_added,
+        favorites_removed,
+        favorites_reordered,
+        usage_added,
+        usage_updated,
+        usage_removed,
+        launches_before: launches(&current.usage),
+        launches_after: launches(&planned.usage),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn app(bundle_id: &str) -> AppInfo {
+        AppInfo {
+            name: bundle_id.to_string(),
+            bundle_id: bundle_id.to_string(),
+            path: format!("/Applications/{}.app", bundle_id),
+            icon_path: None,
+            icon_key: None,
+            category: None,
+            version: None,
+            alternates: Vec::new(),
+            running: false,
+        }
+    }
+
+    fn usage(bundle_id: &str, days: &[(&str, u32)]) -> AppUsage {
+        let daily: BTreeMap<String, u32> = days
+            .iter()
+            .map(|(day, count)| (day.to_string(), *count))
+            .collect();
+        AppUsage {
+            bundle_id: bundle_id.to_string(),
+            launch_count: daily.values().sum(),
+            first_launched: days
+                .first()
+                .map(|(day, _)| format!("{}T08:00:00+00:00", day)),
+            last_launched: days
+                .last()
+                .map(|(day, _)| format!("{}T20:00:00+00:00", day)),
+            daily,
+        }
+    }
+
+    fn state(favorites: &[&str], excluded: &[&str], usage: Vec<AppUsage>) -> StateArchive {
+        let mut settings = Settings::default();
+        settings.usage_tracking.excluded = excluded.iter().map(|id| id.to_string()).collect();
+        StateArchive::new(
+            settings,
+            favorites.iter().map(|id| id.to_string()).collect(),
+            usage,
+        )
+    }
+
+    fn ids(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn merge_sums_launch_counts() {
+        let current = state(
+            &[],
+            &[],
+            vec![usage("a", &[("2026-01-01", 2), ("2026-01-03", 1)])],
+        );
+        let archive = state(
+            &[],
+            &[],
+            vec![usage("a", &[("2026-01-02", 4), ("2026-01-03", 2)])],
+        );
+
+        let planned = plan_import(&current, archive, ImportMode::Merge, &[app("a")]);
+        let merged = &planned.usage[0];
+        assert_eq!(merged.launch_count, 9);
+        assert_eq!(merged.daily["2026-01-03"], 3);
+        assert_eq!(
+            merged.first_launched.as_deref(),
+            Some("2026-01-01T08:00:00+00:00")
+        );
+        assert_eq!(
+            merged.last_launched.as_deref(),
+            Some("2026-01-03T20:00:00+00:00")
+        );
+    }
+
+    #[test]
+    fn merge_unions_favorites_and_exclusions() {
+        let current = state(&["a", "b"], &["x"], vec![]);
+        let archive = state(&["c", "a"], &["y", "x"], vec![]);
+        let apps = [app("a"), app("b"), app("c")];
+
+        let planned = plan_import(&current, archive, ImportMode::Merge, &apps);
+        assert_eq!(planned.favorites, ids(&["a", "b", "c"]));
+        assert_eq!(planned.settings.usage_tracking.excluded, ids(&["x", "y"]));
+    }
+
+    #[test]
+    fn skips_favorites_that_are_not_indexed() {
+        let current = state(&["a"], &[], vec![]);
+        let archive = state(&["gone", "b"], &[], vec![]);
+        let apps = [app("a"), app("b")];
+
+        let merged = plan_import(&current, archive.clone(), ImportMode::Merge, &apps);
+        assert_eq!(merged.favorites, ids(&["a", "b"]));
+        let replaced = plan_import(&current, archive, ImportMode::Replace, &apps);
+        assert_eq!(replaced.favorites, ids(&["b"]));
+    }
+
+    #[test]
+    fn drops_usage_of_excluded_apps() {
+        let current = state(&[], &[], vec![usage("a", &[("2026-01-01", 1)])]);
+        let archive = state(&[], &["a"], vec![usage("b", &[("2026-01-01", 1)])]);
+
+        let planned = plan_import(&current, archive, ImportMode::Merge, &[]);
+        let kept: Vec<&str> = planned.usage.iter().map(|u| u.bundle_id.as_str()).collect();
+        assert_eq!(kept, vec!["b"]);
+    }
+
+    #[test]
+    fn replace_keeps_the_local_usage_file() {
+        let current = state(&[], &[], vec![]);
+        let mut archive = state(&[], &[], vec![]);
+        archive.settings.usage_file = "/elsewhere/usage.json".to_string();
+        archive.settings.hide_on_blur = false;
+
+        let planned = plan_import(&current, archive, ImportMode::Replace, &[]);
+        assert_eq!(planned.settings.usage_file, current.settings.usage_file);
+        let diff = diff(&current, &planned, ImportMode::Replace, false);
+        assert_eq!(diff.settings_changed, ids(&["hide_on_blur"]));
+    }
+
+    #[test]
+    fn diff_reports_each_change() {
+        let current = state(
+            &["a", "b", "c"],
+            &[],
+            vec![
+                usage("a", &[("2026-01-01", 1)]),
+                usage("b", &[("2026-01-01", 2)]),
+            ],
+        );
+        let mut planned = state(
+            &["c", "a", "d"],
+            &[],
+            vec![
+                usage("a", &[("2026-01-01", 1)]),
+                usage("c", &[("2026-01-02", 5)]),
+            ],
+        );
+        planned.settings = current.settings.clone();
+
+        let diff = diff(&current, &planned, ImportMode::Replace, true);
+        assert!(diff.settings_changed.is_empty());
+        assert_eq!(diff.favorites_added, ids(&["d"]));
+        assert_eq!(diff.favorites_removed, ids(&["b"]));
+        assert!(diff.favorites_reordered);
+        assert_eq!(diff.usage_added, ids(&["c"]));
+        assert!(diff.usage_updated.is_empty());
+        assert_eq!(diff.usage_removed, ids(&["b"]));
+        assert_eq!((diff.launches_before, diff.launches_after), (3, 6));
+        assert!(diff.favorites_changed() && diff.usage_changed());
+    }
+
+    #[test]
+    fn diff_of_an_unchanged_state_is_empty() {
+        let current = state(&["a", "b"], &[], vec![usage("a", &[("2026-01-01", 1)])]);
+
+        let diff = diff(&current, &current.clone(), ImportMode::Merge, true);
+        assert!(diff.settings_changed.is_empty());
+        assert!(!diff.favorites_changed());
+        assert!(!diff.usage_changed());
+    }
+
+    #[test]
+    fn refuses_archives_from_newer_builds() {
+        let mut archive = serde_json::to_value(state(&[], &[], vec![])).unwrap();
+        archive["version"] = (ARCHIVE_VERSION + 1).into();
+
+        let error = StateArchive::parse(&archive.to_string()).unwrap_err();
+        assert!(error.contains("not supported"), "{}", error);
+    }
+}
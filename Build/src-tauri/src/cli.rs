@@ -0,0 +1,843 @@
+use crate::backup::{self, ImportDiff, ImportMode, StateArchive};
+use crate::cmd;
+use crate::discovery::AppInfo;
+use crate::favorites::FavoritesStore;
+use crate::index::AppIndex;
+use crate::launcher::{LaunchOutcome, LaunchReport, OpenOptions};
+use crate::running;
+use crate::search;
+use crate::settings::{self, Settings};
+use crate::single_instance;
+use crate::stats::{self, ExportFormat, GroupBy, StatsRange, UsageReport};
+use crate::unused::{self, BundleSizes, UnusedReport};
+use crate::usage;
+use serde::Serialize;
+use std::fmt::Write as _;
+use std::fs;
+use std::io::Write as _;
+use std::sync::mpsc;
+
+/// Exit codes are part of the command-line interface; scripts rely on them
+pub const EXIT_OK: i32 = 0;
+/// Something went wrong while carrying out the command
+pub const EXIT_FAILURE: i32 = 1;
+/// The command line couldn't be parsed
+pub const EXIT_USAGE: i32 = 2;
+/// The named app isn't indexed, or a search matched nothing
+pub const EXIT_NOT_FOUND: i32 = 3;
+
+/// How long `apps unused` waits for a launch unless `--days` says otherwise
+const DEFAULT_UNUSED_DAYS: u32 = 90;
+
+const USAGE: &str = "\
+Usage:
+  relaunchpad [--toggle | --show | --hide | --search <query>]...
+  relaunchpad list [--json]
+  relaunchpad search <query> [--json]
+  relaunchpad open <bundle-id> [--focus] [--json]
+  relaunchpad usage stats [--week | --month | --year] [--weekly] [--json | --csv]
+  relaunchpad usage pause | resume
+  relaunchpad usage exclude | include <bundle-id>
+  relaunchpad usage forget <bundle-id>
+  relaunchpad usage purge
+  relaunchpad apps unused [--days <n>] [--json]
+  relaunchpad state export <file>
+  relaunchpad state import <file> [--merge] [--dry-run] [--json]
+  relaunchpad help
+
+Without a subcommand the launcher window is started, or the flags are
+forwarded to the instance that is already running. Subcommands run
+without opening a window.
+
+State import replaces settings, favorites and launch history with the
+archive's, or merges them in with --merge. Quit the launcher first.
+
+Exit codes: 0 success, 1 failure, 2 invalid usage, 3 app not found or no
+search matches.";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    /// Start the launcher window, or forward these flags to the running one
+    Launcher(Vec<String>),
+    List {
+        json: bool,
+    },
+    Search {
+        query: String,
+        json: bool,
+    },
+    Open {
+        bundle_id: String,
+        focus: bool,
+        json: bool,
+    },
+    UsageStats {
+        range: StatsRange,
+        group_by: GroupBy,
+        format: Option<ExportFormat>,
+    },
+    /// Stop or resume recording launches
+    UsagePause {
+        paused: bool,
+    },
+    /// Stop or resume recording launches of one app
+    UsageExclude {
+        bundle_id: String,
+        excluded: bool,
+    },
+    /// Delete the launch history of one app
+    UsageForget {
+        bundle_id: String,
+    },
+    /// Delete all launch history
+    UsagePurge,
+    /// Apps not launched in the last `days` days, largest first
+    UnusedApps {
+        days: u32,
+        json: bool,
+    },
+    /// Write settings, favorites and launch history to an archive
+    StateExport {
+        file: String,
+    },
+    /// Replace or merge the stores with an archive's
+    StateImport {
+        file: String,
+        mode: ImportMode,
+        dry_run: bool,
+        json: bool,
+    },
+    Help,
+}
+
+/// Why a subcommand failed, which decides the exit code
+#[derive(Debug, Clone)]
+pub enum CliError {
+    Usage(String),
+    NotFound(String),
+    Failed(String),
+}
+
+impl CliError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CliError::Usage(_) => EXIT_USAGE,
+            CliError::NotFound(_) => EXIT_NOT_FOUND,
+            CliError::Failed(_) => EXIT_FAILURE,
+        }
+    }
+
+    fn message(&self) -> &str {
+        match self {
+            CliError::Usage(message) | CliError::NotFound(message) | CliError::Failed(message) => {
+                message
+            }
+        }
+    }
+}
+
+impl From<String> for CliError {
+    fn from(message: String) -> Self {
+        CliError::Failed(message)
+    }
+}
+
+/// Runs the command line and returns the process exit code
+pub fn main(args: Vec<String>) -> i32 {
+    let result = parse(&args).and_then(|command| match command {
+        Command::Launcher(args) => Ok(crate::run_launcher(args)),
+        Command::Help => print(USAGE).map(|()| EXIT_OK),
+        command => execute(command).map(|()| EXIT_OK),
+    });
+
+    match result {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("relaunchpad: {}", e.message());
+            if let CliError::Usage(_) = e {
+                eprintln!("Run 'relaunchpad help' for usage.");
+            }
+            e.exit_code()
+        }
+    }
+}
+
+pub fn parse(args: &[String]) -> Result<Command, CliError> {
+    let Some((first, rest)) = args.split_first() else {
+        return Ok(Command::Launcher(Vec::new()));
+    };
+
+    let command = match first.as_str() {
+        "help" | "--help" | "-h" => Command::Help,
+        "list" => {
+            let (flags, operands) = split_args(rest, &["--json"])?;
+            no_operands(&operands)?;
+            Command::List {
+                json: flags.contains(&"--json"),
+            }
+        }
+        "search" => {
+            let (flags, operands) = split_args(rest, &["--json"])?;
+            Command::Search {
+                query: single_operand(&operands, "a search query")?,
+                json: flags.contains(&"--json"),
+            }
+        }
+        "open" => {
+            let (flags, operands) = split_args(rest, &["--focus", "--json"])?;
+            Command::Open {
+                bundle_id: single_operand(&operands, "a bundle id")?,
+                focus: flags.contains(&"--focus"),
+                json: flags.contains(&"--json"),
+            }
+        }
+        "usage" => match rest.split_first() {
+            Some((sub, rest)) if sub == "stats" => {
+                let (flags, operands) = split_args(
+                    rest,
+                    &["--week", "--month", "--year", "--weekly", "--json", "--csv"],
+                )?;
+                no_operands(&operands)?;
+                usage_stats_command(&flags)?
+            }
+            Some((sub, rest)) if sub == "pause" || sub == "resume" => {
+                let (_, operands) = split_args(rest, &[])?;
+                no_operands(&operands)?;
+                Command::UsagePause {
+                    paused: sub == "pause",
+                }
+            }
+            Some((sub, rest)) if sub == "exclude" || sub == "include" => {
+                let (_, operands) = split_args(rest, &[])?;
+                Command::UsageExclude {
+                    bundle_id: single_operand(&operands, "a bundle id")?,
+                    excluded: sub == "exclude",
+                }
+            }
+            Some((sub, rest)) if sub == "forget" => {
+                let (_, operands) = split_args(rest, &[])?;
+                Command::UsageForget {
+                    bundle_id: single_operand(&operands, "a bundle id")?,
+                }
+            }
+            Some((sub, rest)) if sub == "purge" => {
+                let (_, operands) = split_args(rest, &[])?;
+                no_operands(&operands)?;
+                Command::UsagePurge
+            }
+            Some((sub, _)) => {
+                return Err(CliError::Usage(format!("Unknown usage command '{}'", sub)))
+            }
+            None => return Err(CliError::Usage("Expected a usage command".to_string())),
+        },
+        "apps" => match rest.split_first() {
+            Some((sub, rest)) if sub == "unused" => {
+                let (flags, operands) = split_args(rest, &["--days", "--json"])?;
+                let days = if flags.contains(&"--days") {
+                    let days = single_operand(&operands, "a number of days after --days")?;
+                    days.parse().map_err(|_| {
+                        CliError::Usage(format!("'{}' is not a number of days", days))
+                    })?
+                } else {
+                    no_operands(&operands)?;
+                    DEFAULT_UNUSED_DAYS
+                };
+                Command::UnusedApps {
+                    days,
+                    json: flags.contains(&"--json"),
+                }
+            }
+            Some((sub, _)) => {
+                return Err(CliError::Usage(format!("Unknown apps command '{}'", sub)))
+            }
+            None => return Err(CliError::Usage("Expected an apps command".to_string())),
+        },
+        "state" => match rest.split_first() {
+            Some((sub, rest)) if sub == "export" => {
+                let (_, operands) = split_args(rest, &[])?;
+                Command::StateExport {
+                    file: single_operand(&operands, "an archive file")?,
+                }
+            }
+            Some((sub, rest)) if sub == "import" => {
+                let (flags, operands) = split_args(rest, &["--merge", "--dry-run", "--json"])?;
+                Command::StateImport {
+                    file: single_operand(&operands, "an archive file")?,
+                    mode: if flags.contains(&"--merge") {
+                        ImportMode::Merge
+                    } else {
+                        ImportMode::Replace
+                    },
+                    dry_run: flags.contains(&"--dry-run"),
+                    json: flags.contains(&"--json"),
+                }
+            }
+            Some((sub, _)) => {
+                return Err(CliError::Usage(format!("Unknown state command '{}'", sub)))
+            }
+            None => return Err(CliError::Usage("Expected a state command".to_string())),
+        },
+        flag if flag.starts_with('-') => {
+            single_instance::parse_args(args).map_err(CliError::Usage)?;
+            Command::Launcher(args.to_vec())
+        }
+        other => return Err(CliError::Usage(format!("Unknown command '{}'", other))),
+    };
+
+    Ok(command)
+}
+
+/// Splits subcommand arguments into the given flags, all of which must be in
+/// `allowed`, and the remaining operands
+fn split_args<'a>(
+    args: &'a [String],
+    allowed: &[&str],
+) -> Result<(Vec<&'a str>, Vec<&'a str>), CliError> {
+    let (flags, operands): (Vec<&str>, Vec<&str>) = args
+        .iter()
+        .map(String::as_str)
+        .partition(|arg| arg.starts_with("--"));
+
+    match flags.iter().find(|flag| !allowed.contains(*flag)) {
+        Some(flag) => Err(CliError::Usage(format!("Unknown option '{}'", flag))),
+        None => Ok((flags, operands)),
+    }
+}
+
+fn usage_stats_command(flags: &[&str]) -> Result<Command, CliError> {
+    let ranges: Vec<StatsRange> = flags
+        .iter()
+        .filter_map(|flag| match *flag {
+            "--week" => Some(StatsRange::Week),
+            "--month" => Some(StatsRange::Month),
+            "--year" => Some(StatsRange::Year),
+            _ => None,
+        })
+        .collect();
+    if ranges.len() > 1 {
+        return Err(CliError::Usage(
+            "Only one of --week, --month and --year can be given".to_string(),
+        ));
+    }
+    if flags.contains(&"--json") && flags.contains(&"--csv") {
+        return Err(CliError::Usage(
+            "Only one of --json and --csv can be given".to_string(),
+        ));
+    }
+
+    Ok(Command::UsageStats {
+        range: ranges.first().copied().unwrap_or_default(),
+        group_by: if flags.contains(&"--weekly") {
+            GroupBy::Week
+        } else {
+            GroupBy::Day
+        },
+        format: if flags.contains(&"--json") {
+            Some(ExportFormat::Json)
+        } else if flags.contains(&"--csv") {
+            Some(ExportFormat::Csv)
+        } else {
+            None
+        },
+    })
+}
+
+fn no_operands(operands: &[&str]) -> Result<(), CliError> {
+    match operands.first() {
+        Some(extra) => Err(CliError::Usage(format!("Unexpected argument '{}'", extra))),
+        None => Ok(()),
+    }
+}
+
+fn single_operand(operands: &[&str], what: &str) -> Result<String, CliError> {
+    match operands {
+        [operand] => Ok(operand.to_string()),
+        [] => Err(CliError::Usage(format!("Expected {}", what))),
+        [_, extra, ..] => Err(CliError::Usage(format!("Unexpected argument '{}'", extra))),
+    }
+}
+
+fn execute(command: Command) -> Result<(), CliError> {
+    let settings = Settings::load();
+
+    match command {
+        Command::List { json } => print_apps(load_apps(&settings)?, json),
+        Command::Search { query, json } => {
+            let favorites = FavoritesStore::load().get();
+            let matches = search::search_apps(&load_apps(&settings)?, &query, &favorites);
+            if matches.is_empty() {
+                return Err(CliError::NotFound(format!("No apps match '{}'", query)));
+            }
+            print_apps(matches, json)
+        }
+        Command::Open {
+            bundle_id,
+            focus,
+            json,
+        } => {
+            let report = open(&settings, &bundle_id, focus)?;
+            if json {
+                print_json(&report)?;
+            }
+            let report = report.into_result()?;
+            let verb = match report.outcome {
+                LaunchOutcome::Focused => "Focused",
+                _ => "Launched",
+            };
+            if json {
+                Ok(())
+            } else {
+                print(&format!("{} {}", verb, report.bundle_id))
+            }
+        }
+        Command::UsageStats {
+            range,
+            group_by,
+            format,
+        } => {
+            let usage_data = usage::load_app_usage(&settings.usage_file_path())?;
+            let report = stats::usage_report(
+                &usage_data,
+                &load_apps(&settings)?,
+                range,
+                group_by,
+                chrono::Local::now().date_naive(),
+            );
+            match format {
+                Some(format) => print(stats::export_report(&report, format)?.trim_end()),
+                None => print_usage(&report),
+            }
+        }
+        Command::UsagePause { paused } => {
+            update_settings(|settings| settings.usage_tracking.paused = paused)?;
+            print(if paused {
+                "Usage tracking paused"
+            } else {
+                "Usage tracking resumed"
+            })
+        }
+        Command::UsageExclude {
+            bundle_id,
+            excluded,
+        } => {
+            update_settings(|settings| {
+                let tracking = &mut settings.usage_tracking;
+                tracking.excluded.retain(|id| *id != bundle_id);
+                if excluded {
+                    tracking.excluded.push(bundle_id.clone());
+                }
+            })?;
+            print(&if excluded {
+                format!("Excluded {} from usage tracking", bundle_id)
+            } else {
+                format!("Included {} in usage tracking", bundle_id)
+            })
+        }
+        Command::UsageForget { bundle_id } => {
+            if usage::forget_app(&settings.usage_file_path(), &bundle_id)? {
+                print(&format!("Forgot the usage of {}", bundle_id))
+            } else {
+                print(&format!("No usage recorded for {}", bundle_id))
+            }
+        }
+        Command::UsagePurge => {
+            usage::purge(&settings.usage_file_path())?;
+            print("Deleted all usage history")
+        }
+        Command::UnusedApps { days, json } => {
+            let usage_data = usage::load_app_usage(&settings.usage_file_path())?;
+            let unused = unused::unused_apps(
+                &load_apps(&settings)?,
+                &usage_data,
+                days,
+                chrono::Utc::now(),
+            );
+            let sizes = BundleSizes::load();
+            let report = unused::with_sizes(unused, &sizes, days);
+            if let Err(e) = sizes.save() {
+                eprintln!("Failed to cache bundle sizes: {}", e);
+            }
+            if json {
+                print_json(&report)
+            } else {
+                print_unused(&report)
+            }
+        }
+        Command::StateExport { file } => {
+            let settings = settings::load_settings()?;
+            let usage_data = usage::load_app_usage(&settings.usage_file_path())?;
+            let archive = StateArchive::new(settings, FavoritesStore::load().get(), usage_data);
+            let json = serde_json::to_string_pretty(&archive)
+                .map_err(|e| format!("Failed to serialize archive: {}", e))?;
+            fs::write(&file, json).map_err(|e| format!("Failed to write {}: {}", file, e))?;
+            print(&format!("Exported state to {}", file))
+        }
+        Command::StateImport {
+            file,
+            mode,
+            dry_run,
+            json,
+        } => {
+            let diff = import_state(&file, mode, dry_run)?;
+            if json {
+                print_json(&diff)
+            } else {
+                print_import(&diff)
+            }
+        }
+        Command::Help | Command::Launcher(_) => {
+            unreachable!("handled by cli::main without loading settings")
+        }
+    }
+}
+
+/// The indexed apps, using the snapshot the launcher keeps when there is one
+fn load_apps(settings: &Settings) -> Result<Vec<AppInfo>, CliError> {
+    let index = AppIndex::load();
+    if !index.is_empty() {
+        return Ok(index.apps());
+    }
+
+    let apps = cmd::scan_apps(&settings.discovery, |_| {})?;
+    index.replace(apps.clone());
+    Ok(apps)
+}
+
+/// Changes and saves the settings file. A running launcher picks up the
+/// change from the file. A file that doesn't load is left alone rather than
+/// replaced by defaults.
+fn update_settings(change: impl FnOnce(&mut Settings)) -> Result<(), CliError> {
+    let mut settings = settings::load_settings()?;
+    change(&mut settings);
+    settings.validate()?;
+    settings.save()?;
+    Ok(())
+}
+
+/// Imports an archive the way the launcher's `import_state` does. The
+/// launcher keeps favorites in memory and would write over them, so a real
+/// import is refused while it runs.
+fn import_state(file: &str, mode: ImportMode, dry_run: bool) -> Result<ImportDiff, CliError> {
+    let contents =
+        fs::read_to_string(file).map_err(|e| format!("Failed to read {}: {}", file, e))?;
+    let archive = StateArchive::parse(&contents)?;
+    if !dry_run && single_instance::is_running() {
+        return Err(CliError::Failed(
+            "Quit the launcher before importing state".to_string(),
+        ));
+    }
+
+    let settings = settings::load_settings()?;
+    let usage_data = usage::load_app_usage(&settings.usage_file_path())?;
+    let favorites = FavoritesStore::load();
+    let current = StateArchive::new(settings, favorites.get(), usage_data);
+    let apps = load_apps(&current.settings)?;
+    let planned = backup::plan_import(&current, archive, mode, &apps);
+    let diff = backup::diff(&current, &planned, mode, dry_run);
+    if dry_run {
+        return Ok(diff);
+    }
+
+    if !diff.settings_changed.is_empty() {
+        planned.settings.save()?;
+    }
+    if diff.favorites_changed() {
+        favorites.replace(planned.favorites)?;
+    }
+    if diff.usage_changed() {
+        usage::replace_all(&planned.settings, &planned.usage)?;
+    }
+    Ok(diff)
+}
+
+/// Launches an indexed app the way the launcher does, counting the launch.
+/// Returns once the launch has settled, so an app that crashes right after
+/// starting is reported as such.
+fn open(settings: &Settings, bundle_id: &str, focus: bool) -> Result<LaunchReport, CliError> {
+    let app = load_apps(settings)?
+        .into_iter()
+        .find(|app| app.bundle_id == bundle_id)
+        .ok_or_else(|| CliError::NotFound(format!("'{}' is not an indexed app", bundle_id)))?;
+
+    let (sender, settled) = mpsc::channel();
+    let (report, _) = cmd::launch_and_track(
+        &app,
+        None,
+        &[],
+        &OpenOptions::default(),
+        focus,
+        settings,
+        move |report| {
+            let _ = sender.send(report);
+        },
+    )
+    .map_err(|e| CliError::Failed(e.to_string()))?;
+    Ok(settled.recv().unwrap_or(report))
+}
+
+/// Prints one app per line, or a JSON array that also says which are running
+fn print_apps(mut apps: Vec<AppInfo>, json: bool) -> Result<(), CliError> {
+    if json {
+        running::mark_running(&mut apps);
+        return print_json(&apps);
+    }
+
+    let mut output = String::new();
+    for app in &apps {
+        let _ = writeln!(output, "{}\t{}\t{}", app.name, app.bundle_id, app.path);
+    }
+    print(output.trim_end())
+}
+
+/// Prints launches in the range, bundle id, current streak and last launch
+/// for each app, most launched first
+fn print_usage(report: &UsageReport) -> Result<(), CliError> {
+    let mut output = String::new();
+    for app in &report.apps {
+        let _ = writeln!(
+            output,
+            "{}\t{}\t{}\t{}",
+            app.launches,
+            app.bundle_id,
+            app.current_streak_days,
+            app.last_launched.as_deref().unwrap_or("-")
+        );
+    }
+    print(output.trim_end())
+}
+
+/// Prints size, bundle id, days since the last launch (or install) and path
+/// for each unused app, largest first
+fn print_unused(report: &UnusedReport) -> Result<(), CliError> {
+    let mut output = String::new();
+    for app in &report.apps {
+        let size = match app.size_bytes {
+            Some(bytes) => format!("{:.1} MB", bytes as f64 / 1_000_000.0),
+            None => "-".to_string(),
+        };
+        let days = match app.days_unused {
+            Some(days) => days.to_string(),
+            None => "-".to_string(),
+        };
+        let _ = writeln!(
+            output,
+            "{}\t{}\t{}\t{}",
+            size, app.bundle_id, days, app.path
+        );
+    }
+    print(output.trim_end())
+}
+
+/// Prints one line per kind of change an import makes, or would make
+fn print_import(diff: &ImportDiff) -> Result<(), CliError> {
+    let mut output = String::new();
+    let lists = [
+        ("settings", &diff.settings_changed),
+        ("favorites added", &diff.favorites_added),
+        ("favorites removed", &diff.favorites_removed),
+        ("usage added", &diff.usage_added),
+        ("usage updated", &diff.usage_updated),
+        ("usage removed", &diff.usage_removed),
+    ];
+    for (label, ids) in lists {
+        if !ids.is_empty() {
+            let _ = writeln!(output, "{}\t{}", label, ids.join(", "));
+        }
+    }
+    if diff.favorites_reordered {
+        let _ = writeln!(output, "favorites reordered");
+    }
+    if diff.launches_before != diff.launches_after {
+        let _ = writeln!(
+            output,
+            "launches\t{} -> {}",
+            diff.launches_before, diff.launches_after
+        );
+    }
+
+    if output.is_empty() {
+        output.push_str("Nothing to import\n");
+    } else if diff.dry_run {
+        output.push_str("Dry run, nothing was written\n");
+    }
+    print(output.trim_end())
+}
+
+fn print_json<T: Serialize + ?Sized>(value: &T) -> Result<(), CliError> {
+    let json = serde_json::to_string_pretty(value)
+        .map_err(|e| format!("Failed to serialize output: {}", e))?;
+    print(&json)
+}
+
+/// Writes to stdout, staying quiet when the reader has gone away (e.g. `| head`)
+fn print(text: &str) -> Result<(), CliError> {
+    if text.is_empty() {
+        return Ok(());
+    }
+    let _ = writeln!(std::io::stdout().lock(), "{}", text);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_line(line: &str) -> Result<Command, CliError> {
+        let args: Vec<String> = line.split_whitespace().map(String::from).collect();
+        parse(&args)
+    }
+
+    fn usage_error(line: &str) -> String {
+        match parse_line(line) {
+            Err(CliError::Usage(message)) => message,
+            other => panic!("expected a usage error for '{}', got {:?}", line, other),
+        }
+    }
+
+    #[test]
+    fn parses_subcommands_and_their_flags() {
+        assert_eq!(parse_line("").unwrap(), Command::Launcher(Vec::new()));
+        assert_eq!(
+            parse_line("list --json").unwrap(),
+            Command::List { json: true }
+        );
+        assert_eq!(
+            parse_line("open com.example.App --focus").unwrap(),
+            Command::Open {
+                bundle_id: "com.example.App".to_string(),
+                focus: true,
+                json: false,
+            }
+        );
+        assert_eq!(
+            parse_line("usage stats --month --weekly --csv").unwrap(),
+            Command::UsageStats {
+                range: StatsRange::Month,
+                group_by: GroupBy::Week,
+                format: Some(ExportFormat::Csv),
+            }
+        );
+        assert_eq!(parse_line("help").unwrap(), Command::Help);
+    }
+
+    #[test]
+    fn forwards_launcher_flags() {
+        assert_eq!(
+            parse_line("--show --search mail").unwrap(),
+            Command::Launcher(vec![
+                "--show".to_string(),
+                "--search".to_string(),
+                "mail".to_string(),
+            ])
+        );
+        assert_eq!(
+            usage_error("--frobnicate"),
+            "Unknown argument '--frobnicate'"
+        );
+    }
+
+    #[test]
+    fn rejects_conflicting_flags() {
+        assert!(usage_error("usage stats --week --year").contains("--week, --month and --year"));
+        assert!(usage_error("usage stats --json --csv").contains("--json and --csv"));
+    }
+
+    #[test]
+    fn rejects_unknown_commands_and_options() {
+        assert_eq!(usage_error("frobnicate"), "Unknown command 'frobnicate'");
+        assert_eq!(usage_error("list --verbose"), "Unknown option '--verbose'");
+        assert_eq!(
+            usage_error("open com.example.App --new"),
+            "Unknown option '--new'"
+        );
+        assert_eq!(usage_error("usage"), "Expected a usage command");
+        assert_eq!(usage_error("usage wipe"), "Unknown usage command 'wipe'");
+    }
+
+    #[test]
+    fn checks_operand_counts() {
+        assert_eq!(usage_error("list extra"), "Unexpected argument 'extra'");
+        assert_eq!(usage_error("search"), "Expected a search query");
+        assert_eq!(
+            usage_error("search mail extra"),
+            "Unexpected argument 'extra'"
+        );
+        assert_eq!(usage_error("open"), "Expected a bundle id");
+    }
+
+    #[test]
+    fn parses_usage_tracking_commands() {
+        assert_eq!(
+            parse_line("usage pause").unwrap(),
+            Command::UsagePause { paused: true }
+        );
+        assert_eq!(
+            parse_line("usage include com.example.App").unwrap(),
+            Command::UsageExclude {
+                bundle_id: "com.example.App".to_string(),
+                excluded: false,
+            }
+        );
+        assert_eq!(usage_error("usage forget"), "Expected a bundle id");
+        assert_eq!(usage_error("usage purge now"), "Unexpected argument 'now'");
+    }
+
+    #[test]
+    fn parses_state_commands() {
+        assert_eq!(
+            parse_line("state import backup.json --merge --dry-run").unwrap(),
+            Command::StateImport {
+                file: "backup.json".to_string(),
+                mode: ImportMode::Merge,
+                dry_run: true,
+                json: false,
+            }
+        );
+        assert_eq!(usage_error("state export"), "Expected an archive file");
+        assert_eq!(
+            usage_error("state import a.json --force"),
+            "Unknown option '--force'"
+        );
+        assert_eq!(
+            usage_error("state restore file"),
+            "Unknown state command 'restore'"
+        );
+    }
+
+    #[test]
+    fn reads_the_unused_days() {
+        assert_eq!(
+            parse_line("apps unused").unwrap(),
+            Command::UnusedApps {
+                days: DEFAULT_UNUSED_DAYS,
+                json: false,
+            }
+        );
+        assert_eq!(
+            parse_line("apps unused --days 30 --json").unwrap(),
+            Command::UnusedApps {
+                days: 30,
+                json: true,
+            }
+        );
+        assert_eq!(usage_error("apps unused 30"), "Unexpected argument '30'");
+        assert_eq!(
+            usage_error("apps unused --days soon"),
+            "'soon' is not a number of days"
+        );
+    }
+
+    #[test]
+    fn maps_errors_to_exit_codes() {
+        assert_eq!(CliError::Usage(String::new()).exit_code(), EXIT_USAGE);
+        assert_eq!(
+            CliError::NotFound(String::new()).exit_code(),
+            EXIT_NOT_FOUND
+        );
+        assert_eq!(CliError::Failed(String::new()).exit_code(), EXIT_FAILURE);
+        assert_eq!(
+            CliError::from("broken".to_string()).exit_code(),
+            EXIT_FAILURE
+        );
+    }
+}
@@ -1,204 +1,146 @@
-use plist::Value;
-use serde::{Deserialize, Serialize};
+use crate::backup::{self, ImportDiff, ImportMode, StateArchive};
+use crate::discovery::{self, AppInfo, DiscoveryConfig, DiscoveryProgress};
+use crate::error::CommandError;
+use crate::favorites::FavoritesStore;
+use crate::hotkey;
+use crate::index::AppIndex;
+use crate::launcher::{self, LaunchReport, OpenOptions};
+use crate::quit::{self, QuitReport};
+use crate::running::{self, RunningApp};
+use crate::search;
+use crate::settings::{Settings, SettingsStore};
+use crate::stats::{self, ExportFormat, GroupBy, StatsRange, UsageReport};
+use crate::trash::{self, TrashHistory, UndoEntry};
+use crate::tray;
+use crate::unused::{self, BundleSizes, CleanupFailure, CleanupReport, UnusedReport};
+use crate::usage;
 use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
-use std::process::Command;
-use tauri::{AppHandle, Manager, Window};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State, Window};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
 use image::ImageFormat;
 
-#[derive(Serialize, Debug, Clone)]
-pub struct AppInfo {
-    pub name: String,
-    pub bundle_id: String,
-    pub path: String,
-    pub icon_path: Option<String>,
-    pub category: Option<String>,
-    pub version: Option<String>,
-}
-
-// Define the AppUsage struct with proper serde derives
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct AppUsage {
-    pub bundle_id: String,
-    pub launch_count: u32,
-    pub last_launched: Option<String>,
-}
-
-/// Lists all installed macOS applications
+/// Lists all installed macOS applications.
+/// When a snapshot from a previous run exists it is returned immediately and
+/// a background rescan emits `apps-changed` with whatever differs. Otherwise
+/// bundles are parsed off the main thread and streamed to the UI as
+/// `list-apps-progress` events while the scan runs.
 #[tauri::command]
-pub fn list_apps() -> Result<Vec<AppInfo>, String> {
-    println!("list_apps() called"); // Added logging
-
-    let output = Command::new("mdfind")
-        .arg("kMDItemContentType == 'com.apple.application-bundle'")
-        .output()
-        .map_err(|e| format!("Failed to run mdfind: {}", e))?;
-
-    if !output.status.success() {
-        return Err("mdfind command failed".to_string());
+pub async fn list_apps(
+    app: AppHandle,
+    index: State<'_, AppIndex>,
+    settings: State<'_, SettingsStore>,
+) -> Result<Vec<AppInfo>, String> {
+    if !index.is_empty() {
+        let mut apps = index.apps();
+        allow_icon_assets(&app, &apps);
+        spawn_rescan(app);
+        running::mark_running(&mut apps);
+        return Ok(apps);
     }
 
-    let paths = String::from_utf8_lossy(&output.stdout);
-    let mut results = Vec::new();
-
-    for path in paths.lines() {
-        if path.trim().is_empty() {
-            continue;
-        }
+    let emitter = app.clone();
+    let config = settings.get().discovery;
+    let mut apps = tauri::async_runtime::spawn_blocking(move || {
+        scan_apps(&config, |progress| {
+            let _ = emitter.emit("list-apps-progress", progress);
+        })
+    })
+    .await
+    .map_err(|e| format!("App discovery task failed: {}", e))??;
+
+    allow_icon_assets(&app, &apps);
+    index.replace(apps.clone());
+    tray::refresh(&app);
+    running::mark_running(&mut apps);
+    Ok(apps)
+}
 
-        if let Some(app_info) = parse_app_info(path) {
-            results.push(app_info);
-        }
+/// Rescans in the background and emits `apps-changed` if the index moved
+pub(crate) fn spawn_rescan(app: AppHandle) {
+    if !app.state::<AppIndex>().begin_rescan() {
+        return;
     }
 
-    // Sort by name for consistency
-    results.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+    tauri::async_runtime::spawn_blocking(move || {
+        let index = app.state::<AppIndex>();
+        let config = app.state::<SettingsStore>().get().discovery;
 
-    Ok(results)
-}
+        match scan_apps(&config, |_| {}) {
+            Ok(apps) => {
+                allow_icon_assets(&app, &apps);
+                let diff = index.replace(apps);
+                if !diff.is_empty() {
+                    let _ = app.emit("apps-changed", diff);
+                }
+            }
+            Err(e) => eprintln!("Background app rescan failed: {}", e),
+        }
 
-fn parse_app_info(app_path: &str) -> Option<AppInfo> {
-    let plist_path = format!("{}/Contents/Info.plist", app_path);
-
-    // Check if the plist file exists
-    if !Path::new(&plist_path).exists() {
-        return None;
-    }
-
-    let file = fs::File::open(&plist_path).ok()?;
-    let info = Value::from_reader_xml(file).ok()?;
-    let dict = info.as_dictionary()?;
-
-    // Get app name (prefer display name over bundle name)
-    let name = dict
-        .get("CFBundleDisplayName")
-        .or_else(|| dict.get("CFBundleName"))
-        .and_then(|v| v.as_string())
-        .filter(|s| !s.is_empty())
-        .unwrap_or("Unnamed")
-        .to_string();
-
-    // Get bundle identifier
-    let bundle_id = dict
-        .get("CFBundleIdentifier")
-        .and_then(|v| v.as_string())
-        .filter(|s| !s.is_empty())?
-        .to_string();
-
-    // Get version
-    let version = dict
-        .get("CFBundleShortVersionString")
-        .or_else(|| dict.get("CFBundleVersion"))
-        .and_then(|v| v.as_string())
-        .map(|s| s.to_string());
-
-    // Get category
-    let category = dict
-        .get("LSApplicationCategoryType")
-        .and_then(|v| v.as_string())
-        .map(|s| format_app_category(s))
-        .or_else(|| guess_category_from_path(app_path));
-
-    // Get icon path
-    let icon_path = get_app_icon_path(dict, app_path);
-
-    Some(AppInfo {
-        name,
-        bundle_id,
-        path: app_path.to_string(),
-        icon_path,
-        category,
-        version,
-    })
+        index.end_rescan();
+    });
 }
 
-fn format_app_category(category: &str) -> String {
-    match category {
-        "public.app-category.productivity" => "Productivity".to_string(),
-        "public.app-category.graphics-design" => "Graphics & Design".to_string(),
-        "public.app-category.developer-tools" => "Developer Tools".to_string(),
-        "public.app-category.entertainment" => "Entertainment".to_string(),
-        "public.app-category.education" => "Education".to_string(),
-        "public.app-category.lifestyle" => "Lifestyle".to_string(),
-        "public.app-category.utilities" => "Utilities".to_string(),
-        "public.app-category.games" => "Games".to_string(),
-        "public.app-category.social-networking" => "Social Networking".to_string(),
-        "public.app-category.finance" => "Finance".to_string(),
-        "public.app-category.photography" => "Photography".to_string(),
-        "public.app-category.music" => "Music".to_string(),
-        "public.app-category.video" => "Video".to_string(),
-        _ => "Other".to_string(),
-    }
-}
-
-fn guess_category_from_path(path: &str) -> Option<String> {
-    if path.contains("Utilities") {
-        Some("Utilities".to_string())
-    } else if path.contains("Games") {
-        Some("Games".to_string())
-    } else if path.contains("Graphics") || path.contains("Design") {
-        Some("Graphics & Design".to_string())
-    } else {
-        None
+/// Lets the asset protocol serve the resource folders discovered icons live
+/// in. The static scope in `tauri.conf.json` is empty, so nothing else on
+/// disk is reachable through `asset:` URLs.
+fn allow_icon_assets(app: &AppHandle, apps: &[AppInfo]) {
+    let scope = app.asset_protocol_scope();
+    for dir in apps
+        .iter()
+        .filter_map(|info| info.icon_path.as_deref())
+        .filter_map(|icon| Path::new(icon).parent())
+    {
+        if let Err(e) = scope.allow_directory(dir, false) {
+            eprintln!("Failed to allow icon directory {}: {}", dir.display(), e);
+        }
     }
 }
 
-fn get_app_icon_path(dict: &plist::Dictionary, app_path: &str) -> Option<String> {
-    println!("get_app_icon_path() called"); // Added logging
+pub(crate) fn scan_apps<F>(config: &DiscoveryConfig, on_progress: F) -> Result<Vec<AppInfo>, String>
+where
+    F: Fn(DiscoveryProgress) + Sync,
+{
+    let paths = discovery::find_bundle_paths(config)?;
+    let parsed = discovery::parse_bundles(&paths, on_progress);
+    let mut results = discovery::group_by_bundle_id(parsed, config);
 
-    // Try different icon keys
-    let icon_keys = ["CFBundleIconFile", "CFBundleIconName", "CFBundleIcons"];
-
-    for key in &icon_keys {
-        println!("Trying key: {}", key); // Added logging
-        if let Some(icon_value) = dict.get(*key) {
-            println!("Value found for key: {}", key); // Added logging
-            if let Some(icon_name) = icon_value.as_string() {
-                let mut icon_file = icon_name.to_string();
-
-                // Ensure .icns extension
-                if !icon_file.ends_with(".icns") {
-                    icon_file.push_str(".icns");
-                }
+    // Sort by name for consistency
+    results.sort_by_key(|app| app.name.to_lowercase());
 
-                let icon_path = format!("{}/Contents/Resources/{}", app_path, icon_file);
-                println!("Trying icon path: {}", icon_path); // Added logging
+    Ok(results)
+}
 
-                // Check if the icon file actually exists
-                if Path::new(&icon_path).exists() {
-                    println!("Icon found at: {}", icon_path); // Added logging
-                    return Some(icon_path);
-                } else {
-                    println!("Icon not found at: {}", icon_path); // Added logging
-                }
-            }
-        }
+/// Returns the indexed apps, scanning first if nothing has been indexed yet
+async fn indexed_apps(index: &AppIndex, settings: &SettingsStore) -> Result<Vec<AppInfo>, String> {
+    if !index.is_empty() {
+        return Ok(index.apps());
     }
 
-    // Try to find any .icns file in Resources folder as fallback
-    let resources_path = format!("{}/Contents/Resources", app_path);
-    if let Ok(entries) = fs::read_dir(&resources_path) {
-        for entry in entries.flatten() {
-            if let Some(filename) = entry.file_name().to_str() {
-                if filename.ends_with(".icns") {
-                    let icon_path = format!("{}/{}", resources_path, filename);
-                    println!("Trying fallback icon path: {}", icon_path); // Added logging
-                    return Some(icon_path);
-                }
-            }
-        }
-    }
+    let config = settings.get().discovery;
+    let apps = tauri::async_runtime::spawn_blocking(move || scan_apps(&config, |_| {}))
+        .await
+        .map_err(|e| format!("App discovery task failed: {}", e))??;
 
-    None
+    index.replace(apps.clone());
+    Ok(apps)
 }
 
-/// Gets frequently used apps based on usage tracking
-
+/// Gets frequently used apps based on usage tracking. Favorites come first
+/// in their pinned order and are always included; the most launched apps
+/// fill the rest up to the configured number.
 #[tauri::command]
-pub fn get_frequent_apps() -> Result<Vec<AppInfo>, String> {
-    let usage_data = load_app_usage()?;
-    let all_apps = list_apps()?;
+pub async fn get_frequent_apps(
+    index: State<'_, AppIndex>,
+    store: State<'_, SettingsStore>,
+    favorites: State<'_, FavoritesStore>,
+) -> Result<Vec<AppInfo>, String> {
+    let settings = store.get();
+    let usage_data = usage::load_app_usage(&settings.usage_file_path())?;
+    let all_apps = indexed_apps(&index, &store).await?;
 
     // Create a map for quick lookup
     let app_map: HashMap<String, AppInfo> = all_apps
@@ -206,8 +148,15 @@ pub fn get_frequent_apps() -> Result<Vec<AppInfo>, String> {
         .map(|app| (app.bundle_id.clone(), app))
         .collect();
 
+    let favorites = favorites.get();
+    let pinned: Vec<AppInfo> = favorites
+        .iter()
+        .filter_map(|bundle_id| app_map.get(bundle_id).cloned())
+        .collect();
+
     let mut frequent_apps: Vec<(AppInfo, u32)> = usage_data
         .into_iter()
+        .filter(|usage| !favorites.contains(&usage.bundle_id))
         .filter_map(|usage| {
             // Removed type annotation here
             app_map
@@ -216,81 +165,628 @@ pub fn get_frequent_apps() -> Result<Vec<AppInfo>, String> {
         })
         .collect();
 
-    // Sort by launch count (descending) and take top 20
-    frequent_apps.sort_by(|a, b| b.1.cmp(&a.1));
+    // Sort by launch count (descending) and take the configured number
+    frequent_apps.sort_by_key(|(_, launch_count)| std::cmp::Reverse(*launch_count));
 
-    Ok(frequent_apps
+    let remaining = settings.max_frequent_apps.saturating_sub(pinned.len());
+    let mut frequent_apps: Vec<AppInfo> = pinned
         .into_iter()
-        .take(20)
-        .map(|(app, _)| app)
-        .collect())
+        .chain(
+            frequent_apps
+                .into_iter()
+                .take(remaining)
+                .map(|(app, _)| app),
+        )
+        .collect();
+    running::mark_running(&mut frequent_apps);
+
+    Ok(frequent_apps)
 }
 
-/// Tracks app usage for frequent apps feature
+/// Searches the indexed apps by name and bundle id, best matches first.
+/// An empty query lists every app with favorites first.
 #[tauri::command]
-pub fn track_app_usage(bundle_id: String) -> Result<(), String> {
-    // Fixed syntax error: removed invalid generic syntax
-    let mut usage_data: Vec<AppUsage> = load_app_usage().unwrap_or_default();
+pub async fn search_apps(
+    query: String,
+    index: State<'_, AppIndex>,
+    settings: State<'_, SettingsStore>,
+    favorites: State<'_, FavoritesStore>,
+) -> Result<Vec<AppInfo>, String> {
+    let apps = indexed_apps(&index, &settings).await?;
+    let mut matches = search::search_apps(&apps, &query, &favorites.get());
+    running::mark_running(&mut matches);
+    Ok(matches)
+}
 
-    // Find existing entry or create new one
-    if let Some(usage) = usage_data.iter_mut().find(|u| u.bundle_id == bundle_id) {
-        usage.launch_count += 1;
-        usage.last_launched = Some(chrono::Utc::now().to_rfc3339());
-    } else {
-        usage_data.push(AppUsage {
-            bundle_id,
-            launch_count: 1,
-            last_launched: Some(chrono::Utc::now().to_rfc3339()),
-        });
+/// Returns the bundle ids of the favorites in their pinned order
+#[tauri::command]
+pub fn get_favorites(favorites: State<'_, FavoritesStore>) -> Vec<String> {
+    favorites.get()
+}
+
+/// Pins an indexed app at `position`, or last, and emits `favorites-changed`.
+/// A favorite that is already pinned moves to `position`.
+#[tauri::command]
+pub fn add_favorite(
+    app: AppHandle,
+    bundle_id: String,
+    position: Option<usize>,
+    index: State<'_, AppIndex>,
+    favorites: State<'_, FavoritesStore>,
+) -> Result<Vec<String>, CommandError> {
+    if index.get(&bundle_id).is_none() {
+        return Err(CommandError::NotAllowed(format!(
+            "'{}' is not an indexed app",
+            bundle_id
+        )));
     }
 
-    save_app_usage(&usage_data)?;
+    let updated = favorites.add(&bundle_id, position)?;
+    let _ = app.emit("favorites-changed", &updated);
+    Ok(updated)
+}
+
+/// Unpins an app and emits `favorites-changed`
+#[tauri::command]
+pub fn remove_favorite(
+    app: AppHandle,
+    bundle_id: String,
+    favorites: State<'_, FavoritesStore>,
+) -> Result<Vec<String>, String> {
+    let updated = favorites.remove(&bundle_id)?;
+    let _ = app.emit("favorites-changed", &updated);
+    Ok(updated)
+}
+
+/// Rearranges the favorites and emits `favorites-changed`. `bundle_ids` must
+/// list every current favorite exactly once.
+#[tauri::command]
+pub fn reorder_favorites(
+    app: AppHandle,
+    bundle_ids: Vec<String>,
+    favorites: State<'_, FavoritesStore>,
+) -> Result<Vec<String>, String> {
+    let updated = favorites.reorder(bundle_ids)?;
+    let _ = app.emit("favorites-changed", &updated);
+    Ok(updated)
+}
+
+/// Lists the indexed apps that currently have running processes
+#[tauri::command]
+pub async fn list_running_apps(
+    index: State<'_, AppIndex>,
+    settings: State<'_, SettingsStore>,
+) -> Result<Vec<RunningApp>, String> {
+    let apps = indexed_apps(&index, &settings).await?;
+    Ok(running::running_apps(&apps))
+}
+
+/// Tracks app usage for frequent apps feature and emits `usage-changed`.
+/// Nothing is recorded while tracking is paused or for excluded apps.
+#[tauri::command]
+pub fn track_app_usage(
+    app: AppHandle,
+    bundle_id: String,
+    settings: State<'_, SettingsStore>,
+) -> Result<(), String> {
+    if usage::record_launch(&settings.get(), &bundle_id)? {
+        let _ = app.emit("usage-changed", &bundle_id);
+    }
     Ok(())
 }
 
-// Fixed function signature - removed invalid generic syntax
-fn load_app_usage() -> Result<Vec<AppUsage>, String> {
-    let home_dir = std::env::var("HOME").map_err(|_| "Could not find home directory")?;
-    let usage_file = format!("{}/.relaunchpad_usage.json", home_dir);
+/// Pauses or resumes usage tracking and saves it to the settings
+#[tauri::command]
+pub fn set_usage_tracking_paused(
+    app: AppHandle,
+    paused: bool,
+    settings: State<'_, SettingsStore>,
+) -> Result<Settings, String> {
+    let mut updated = settings.get();
+    updated.usage_tracking.paused = paused;
+    apply_settings(&app, updated, true)
+}
 
-    if !Path::new(&usage_file).exists() {
-        return Ok(Vec::new());
+/// Stops or resumes recording launches of one app. Excluding an app keeps
+/// its history; use `forget_app_usage` to delete it.
+#[tauri::command]
+pub fn set_usage_excluded(
+    app: AppHandle,
+    bundle_id: String,
+    excluded: bool,
+    settings: State<'_, SettingsStore>,
+) -> Result<Settings, String> {
+    let mut updated = settings.get();
+    let tracking = &mut updated.usage_tracking;
+    tracking.excluded.retain(|id| *id != bundle_id);
+    if excluded {
+        tracking.excluded.push(bundle_id);
     }
+    apply_settings(&app, updated, true)
+}
 
-    let contents =
-        fs::read_to_string(&usage_file).map_err(|e| format!("Failed to read usage file: {}", e))?;
+/// Deletes the launch history of one app and emits `usage-changed`
+#[tauri::command]
+pub fn forget_app_usage(
+    app: AppHandle,
+    bundle_id: String,
+    settings: State<'_, SettingsStore>,
+) -> Result<(), String> {
+    if usage::forget_app(&settings.get().usage_file_path(), &bundle_id)? {
+        let _ = app.emit("usage-changed", &bundle_id);
+    }
+    Ok(())
+}
 
-    serde_json::from_str(&contents).map_err(|e| format!("Failed to parse usage data: {}", e))
+/// Deletes all launch history and emits `usage-changed`
+#[tauri::command]
+pub fn purge_usage_history(
+    app: AppHandle,
+    settings: State<'_, SettingsStore>,
+) -> Result<(), String> {
+    usage::purge(&settings.get().usage_file_path())?;
+    let _ = app.emit("usage-changed", ());
+    Ok(())
+}
+
+/// Launch statistics over `range` (all time by default), with per-app
+/// launches grouped by day or ISO week
+#[tauri::command]
+pub async fn get_usage_stats(
+    range: Option<StatsRange>,
+    group_by: Option<GroupBy>,
+    index: State<'_, AppIndex>,
+    settings: State<'_, SettingsStore>,
+) -> Result<UsageReport, String> {
+    usage_report(&index, &settings, range, group_by).await
+}
+
+/// The `get_usage_stats` report as a JSON or CSV document, for saving to a file
+#[tauri::command]
+pub async fn export_usage_stats(
+    range: Option<StatsRange>,
+    group_by: Option<GroupBy>,
+    format: ExportFormat,
+    index: State<'_, AppIndex>,
+    settings: State<'_, SettingsStore>,
+) -> Result<String, String> {
+    let report = usage_report(&index, &settings, range, group_by).await?;
+    stats::export_report(&report, format)
+}
+
+async fn usage_report(
+    index: &AppIndex,
+    settings: &SettingsStore,
+    range: Option<StatsRange>,
+    group_by: Option<GroupBy>,
+) -> Result<UsageReport, String> {
+    let usage_data = usage::load_app_usage(&settings.get().usage_file_path())?;
+    let apps = indexed_apps(index, settings).await?;
+
+    Ok(stats::usage_report(
+        &usage_data,
+        &apps,
+        range.unwrap_or_default(),
+        group_by.unwrap_or_default(),
+        chrono::Local::now().date_naive(),
+    ))
+}
+
+/// Bundles settings, favorites and launch history into one versioned JSON
+/// archive for `import_state` on another machine
+#[tauri::command]
+pub fn export_state(
+    settings: State<'_, SettingsStore>,
+    favorites: State<'_, FavoritesStore>,
+) -> Result<String, String> {
+    let archive = current_state(&settings, &favorites)?;
+    serde_json::to_string_pretty(&archive)
+        .map_err(|e| format!("Failed to serialize archive: {}", e))
 }
 
-fn save_app_usage(usage_data: &[AppUsage]) -> Result<(), String> {
-    let home_dir = std::env::var("HOME").map_err(|_| "Could not find home directory")?;
-    let usage_file = format!("{}/.relaunchpad_usage.json", home_dir);
+/// Imports an `export_state` archive, replacing the stores or merging into
+/// them. Favorites of apps that aren't indexed here are skipped. With
+/// `dry_run` nothing is written and the returned diff shows what would
+/// change. Each changed store emits its usual `*-changed` event.
+#[tauri::command]
+pub async fn import_state(
+    app: AppHandle,
+    archive: String,
+    mode: ImportMode,
+    dry_run: Option<bool>,
+    index: State<'_, AppIndex>,
+    settings: State<'_, SettingsStore>,
+    favorites: State<'_, FavoritesStore>,
+) -> Result<ImportDiff, String> {
+    let dry_run = dry_run.unwrap_or(false);
+    let archive = StateArchive::parse(&archive)?;
+    let apps = indexed_apps(&index, &settings).await?;
+    let current = current_state(&settings, &favorites)?;
+    let planned = backup::plan_import(&current, archive, mode, &apps);
+    let diff = backup::diff(&current, &planned, mode, dry_run);
+    if dry_run {
+        return Ok(diff);
+    }
 
-    let json = serde_json::to_string_pretty(usage_data)
-        .map_err(|e| format!("Failed to serialize usage data: {}", e))?;
+    // Settings go first since they are the only store that can be rejected
+    if !diff.settings_changed.is_empty() {
+        apply_settings(&app, planned.settings.clone(), true)?;
+    }
+    if diff.favorites_changed() {
+        let updated = favorites.replace(planned.favorites)?;
+        let _ = app.emit("favorites-changed", &updated);
+    }
+    if diff.usage_changed() {
+        usage::replace_all(&planned.settings, &planned.usage)?;
+        let _ = app.emit("usage-changed", ());
+    }
+
+    Ok(diff)
+}
 
-    fs::write(&usage_file, json).map_err(|e| format!("Failed to write usage file: {}", e))
+fn current_state(
+    settings: &SettingsStore,
+    favorites: &FavoritesStore,
+) -> Result<StateArchive, String> {
+    let settings = settings.get();
+    let usage_data = usage::load_app_usage(&settings.usage_file_path())?;
+    Ok(StateArchive::new(settings, favorites.get(), usage_data))
 }
 
+/// Apps not launched in the last `days` days, largest bundles first. Apps
+/// that were never launched count from when they were installed. Bundle
+/// sizes are cached and only measured again once a bundle changes.
 #[tauri::command]
-pub fn open_app(bundle_id: String) -> Result<(), String> {
+pub async fn get_unused_apps(
+    app: AppHandle,
+    days: u32,
+    index: State<'_, AppIndex>,
+    settings: State<'_, SettingsStore>,
+) -> Result<UnusedReport, String> {
+    let usage_data = usage::load_app_usage(&settings.get().usage_file_path())?;
+    let apps = indexed_apps(&index, &settings).await?;
+    let unused = unused::unused_apps(&apps, &usage_data, days, chrono::Utc::now());
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let sizes = app.state::<BundleSizes>();
+        let report = unused::with_sizes(unused, &sizes, days);
+        if let Err(e) = sizes.save() {
+            eprintln!("Failed to cache bundle sizes: {}", e);
+        }
+        report
+    })
+    .await
+    .map_err(|e| format!("Bundle size task failed: {}", e))
+}
+
+/// Moves the canonical bundles of apps from `get_unused_apps` to the trash.
+/// Nothing happens without `confirm`, and every app must still be indexed,
+/// unused for `days` days, outside system locations and not running. Apps
+/// are trashed one by one, each with an undo entry, and failures reported
+/// alongside the rest; the index is rescanned afterwards.
+#[tauri::command]
+pub async fn trash_unused_apps(
+    app: AppHandle,
+    bundle_ids: Vec<String>,
+    days: u32,
+    confirm: bool,
+    index: State<'_, AppIndex>,
+    settings: State<'_, SettingsStore>,
+    history: State<'_, TrashHistory>,
+) -> Result<CleanupReport, CommandError> {
+    if !confirm {
+        return Err(CommandError::NotAllowed(
+            "Moving apps to the trash has to be confirmed".to_string(),
+        ));
+    }
+
+    let usage_data = usage::load_app_usage(&settings.get().usage_file_path())?;
+    let apps = indexed_apps(&index, &settings).await?;
+    let unused = unused::unused_apps(&apps, &usage_data, days, chrono::Utc::now());
+    let running = running::running_apps(&apps);
+
+    let mut targets = Vec::new();
+    for bundle_id in &bundle_ids {
+        let Some(target) = unused.iter().find(|app| app.bundle_id == *bundle_id) else {
+            return Err(CommandError::NotAllowed(format!(
+                "'{}' is not an indexed app unused for {} days",
+                bundle_id, days
+            )));
+        };
+        let path = removable_path(bundle_id, &target.path, &running)?;
+        targets.push((target.clone(), path));
+    }
+
+    let mut report = CleanupReport::default();
+    for (target, path) in targets {
+        match trash_and_record(&target.bundle_id, &target.name, &path, &history) {
+            Ok(entry) => report.trashed.push(entry),
+            Err(message) => report.failed.push(CleanupFailure {
+                bundle_id: target.bundle_id,
+                message,
+            }),
+        }
+    }
+
+    if !report.trashed.is_empty() {
+        spawn_rescan(app);
+    }
+    Ok(report)
+}
+
+/// Moves the canonical bundle of an indexed app to the platform trash and
+/// records an undo entry for `restore_trashed_app`. Apps inside system
+/// locations and apps that are running are refused. The index is rescanned
+/// afterwards.
+#[tauri::command]
+pub fn trash_app(
+    app: AppHandle,
+    bundle_id: String,
+    index: State<'_, AppIndex>,
+    history: State<'_, TrashHistory>,
+) -> Result<UndoEntry, CommandError> {
+    let target = index.get(&bundle_id).ok_or_else(|| {
+        CommandError::NotAllowed(format!("'{}' is not an indexed app", bundle_id))
+    })?;
+    let running = running::running_apps(std::slice::from_ref(&target));
+    let path = removable_path(&bundle_id, &target.path, &running)?;
+
+    let entry = trash_and_record(&bundle_id, &target.name, &path, &history)?;
+    spawn_rescan(app);
+    Ok(entry)
+}
+
+/// Puts the most recently trashed copy of `bundle_id`, or the most recently
+/// trashed app if none is given, back where it was and rescans the index
+#[tauri::command]
+pub fn restore_trashed_app(
+    app: AppHandle,
+    bundle_id: Option<String>,
+    history: State<'_, TrashHistory>,
+) -> Result<UndoEntry, String> {
+    let entry = history.restore(bundle_id.as_deref())?;
+    spawn_rescan(app);
+    Ok(entry)
+}
+
+/// Apps the launcher moved to the trash that can still be restored, newest last
+#[tauri::command]
+pub fn get_trash_history(history: State<'_, TrashHistory>) -> Vec<UndoEntry> {
+    history.get()
+}
+
+/// The canonical bundle path of an app about to be trashed, refusing
+/// system locations and running apps
+fn removable_path(
+    bundle_id: &str,
+    path: &str,
+    running: &[RunningApp],
+) -> Result<PathBuf, CommandError> {
+    if running.iter().any(|app| app.bundle_id == bundle_id) {
+        return Err(CommandError::NotAllowed(format!(
+            "'{}' is running; quit it first",
+            bundle_id
+        )));
+    }
+    trash::check_removable(Path::new(path)).map_err(CommandError::NotAllowed)
+}
+
+fn trash_and_record(
+    bundle_id: &str,
+    name: &str,
+    path: &Path,
+    history: &TrashHistory,
+) -> Result<UndoEntry, String> {
+    let entry = UndoEntry {
+        bundle_id: bundle_id.to_string(),
+        name: name.to_string(),
+        item: trash::move_to_trash(path)?,
+    };
+    // The app is in the trash either way; without an entry it has to be
+    // restored by hand
+    if let Err(e) = history.record(entry.clone()) {
+        eprintln!("Failed to record undo entry for '{}': {}", bundle_id, e);
+    }
+    Ok(entry)
+}
+
+/// Opens an app by bundle id, launching the canonical copy when the index
+/// knows it. `path` picks a specific installed copy instead and must be the
+/// canonical path or one of the alternates. With `focus_existing`, an app
+/// that is already running is brought to the front instead of relaunched.
+/// Returns once the app is spawned; how the launch settled is emitted as
+/// `launch-finished`. The launch is counted in the usage history. Only apps
+/// in the index can be opened.
+#[tauri::command]
+pub async fn open_app(
+    app: AppHandle,
+    bundle_id: String,
+    path: Option<String>,
+    focus_existing: Option<bool>,
+) -> Result<LaunchReport, CommandError> {
+    open_indexed(&app, &bundle_id, path, focus_existing.unwrap_or(false))
+}
+
+/// `open_app` for callers outside the webview: the tray, D-Bus and the
+/// control socket
+pub fn open_indexed(
+    app: &AppHandle,
+    bundle_id: &str,
+    path: Option<String>,
+    focus_existing: bool,
+) -> Result<LaunchReport, CommandError> {
     if bundle_id.is_empty() {
-        return Err("Bundle ID cannot be empty".to_string());
+        return Err(CommandError::Failed("Bundle ID cannot be empty".to_string()));
     }
 
-    let output = Command::new("open")
-        .arg("-b")
-        .arg(&bundle_id)
-        .output()
-        .map_err(|e| format!("Failed to execute open command: {}", e))?;
+    let indexed = app.state::<AppIndex>().get(bundle_id).ok_or_else(|| {
+        CommandError::NotAllowed(format!("'{}' is not an indexed app", bundle_id))
+    })?;
+    let settings = app.state::<SettingsStore>().get();
+
+    let handle = app.clone();
+    let (report, recorded) = launch_and_track(
+        &indexed,
+        path,
+        &[],
+        &OpenOptions::default(),
+        focus_existing,
+        &settings,
+        move |report| {
+            let _ = handle.emit("launch-finished", &report);
+        },
+    )?;
+
+    if recorded {
+        let _ = app.emit("usage-changed", bundle_id);
+    }
+    Ok(report)
+}
 
-    if !output.status.success() {
-        let error_msg = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("Failed to open app '{}': {}", bundle_id, error_msg));
+/// Opens an indexed app and counts the launch. Every way of picking an app
+/// goes through here: the launcher, tray, command line, D-Bus and control
+/// socket. `path` must be one of the app's copies; `targets` and `options`
+/// are handed to the launch. Bringing a running app to the front counts as a
+/// launch too. `on_settled` gets the final report as
+/// with `launcher::launch`. Returns the report and whether the launch was
+/// recorded.
+pub fn launch_and_track<F>(
+    app: &AppInfo,
+    path: Option<String>,
+    targets: &[String],
+    options: &OpenOptions,
+    focus_existing: bool,
+    settings: &Settings,
+    on_settled: F,
+) -> Result<(LaunchReport, bool), CommandError>
+where
+    F: FnOnce(LaunchReport) + Send + 'static,
+{
+    let focused = match focus_existing {
+        true => launcher::focus_if_running(app),
+        false => None,
+    };
+
+    let report = match focused {
+        Some(report) => {
+            on_settled(report.clone());
+            report
+        }
+        None => {
+            let target = match path {
+                Some(path) if app.has_copy_at(&path) => path,
+                Some(path) => {
+                    return Err(CommandError::NotAllowed(format!(
+                        "'{}' is not a known copy of '{}'",
+                        path, app.bundle_id
+                    )))
+                }
+                None => app.path.clone(),
+            };
+
+            let commands =
+                launcher::launch_commands(&app.bundle_id, Some(&target), targets, options)?;
+            launcher::launch(&app.bundle_id, commands, Some(app.clone()), on_settled)
+                .into_result()?
+        }
+    };
+
+    // Failing to count a launch doesn't make the launch itself fail
+    let recorded = usage::record_launch(settings, &app.bundle_id).unwrap_or_else(|e| {
+        eprintln!("Failed to record launch of '{}': {}", app.bundle_id, e);
+        false
+    });
+    Ok((report, recorded))
+}
+
+/// Asks an app to quit gracefully and reports which processes exited. Only
+/// indexed apps can be quit, and never the session's own processes.
+#[tauri::command]
+pub async fn quit_app(
+    bundle_id: String,
+    index: State<'_, AppIndex>,
+) -> Result<QuitReport, CommandError> {
+    let app = index.get(&bundle_id).ok_or_else(|| {
+        CommandError::NotAllowed(format!("'{}' is not an indexed app", bundle_id))
+    })?;
+
+    tauri::async_runtime::spawn_blocking(move || quit::quit_app(&app, quit::DEFAULT_QUIT_TIMEOUT))
+        .await
+        .map_err(|e| CommandError::Failed(format!("Quit task failed: {}", e)))?
+}
+
+/// Terminates an app, killing processes still alive after `timeout_ms`.
+/// The same targets as `quit_app` are refused.
+#[tauri::command]
+pub async fn force_quit_app(
+    bundle_id: String,
+    timeout_ms: Option<u64>,
+    index: State<'_, AppIndex>,
+) -> Result<QuitReport, CommandError> {
+    let app = index.get(&bundle_id).ok_or_else(|| {
+        CommandError::NotAllowed(format!("'{}' is not an indexed app", bundle_id))
+    })?;
+    let timeout = timeout_ms
+        .map(Duration::from_millis)
+        .unwrap_or(quit::DEFAULT_QUIT_TIMEOUT);
+
+    tauri::async_runtime::spawn_blocking(move || quit::force_quit_app(&app, timeout))
+        .await
+        .map_err(|e| CommandError::Failed(format!("Quit task failed: {}", e)))?
+}
+
+/// Opens files or URLs with an app. `bundle_id_or_path` is the bundle id of
+/// an indexed app or the path of one of its copies (a bundle on macOS, a
+/// desktop entry or executable on Linux); anything else is refused. How the
+/// launch settled is emitted as `launch-finished`, and the launch is counted.
+#[tauri::command]
+pub async fn open_with(
+    app: AppHandle,
+    bundle_id_or_path: String,
+    targets: Vec<String>,
+    options: Option<OpenOptions>,
+    index: State<'_, AppIndex>,
+) -> Result<LaunchReport, CommandError> {
+    if bundle_id_or_path.is_empty() {
+        return Err(CommandError::Failed("App cannot be empty".to_string()));
     }
 
-    Ok(())
+    // By id the canonical copy is preferred over whichever one
+    // LaunchServices picks; by path that exact copy is used
+    let (indexed, path) = match index.get(&bundle_id_or_path) {
+        Some(indexed) => {
+            let path = indexed.path.clone();
+            (indexed, path)
+        }
+        None => match index.find_copy(&bundle_id_or_path) {
+            Some(indexed) => (indexed, bundle_id_or_path),
+            None => {
+                return Err(CommandError::NotAllowed(format!(
+                    "'{}' is not an indexed app",
+                    bundle_id_or_path
+                )))
+            }
+        },
+    };
+    let settings = app.state::<SettingsStore>().get();
+
+    let handle = app.clone();
+    let (report, recorded) = launch_and_track(
+        &indexed,
+        Some(path),
+        &targets,
+        &options.unwrap_or_default(),
+        false,
+        &settings,
+        move |report| {
+            let _ = handle.emit("launch-finished", &report);
+        },
+    )?;
+
+    if recorded {
+        let _ = app.emit("usage-changed", &indexed.bundle_id);
+    }
+    Ok(report)
 }
 
 /// Shows the main window (for global shortcut) - Fixed for Tauri v2
@@ -312,16 +808,136 @@ pub fn hide_main_window(window: Window) -> Result<(), String> {
     window.hide().map_err(|e| e.to_string())
 }
 
-#[cfg(target_os = "macos")]
+/// Hides the main window if it is in front, shows it otherwise
+pub(crate) fn toggle_main_window(app: &AppHandle) -> Result<(), String> {
+    let window = app
+        .get_webview_window("main")
+        .ok_or("Main window not found")?;
+
+    let in_front = window.is_visible().unwrap_or(false) && window.is_focused().unwrap_or(false);
+    if in_front {
+        hide_main_window(window.as_ref().window())
+    } else {
+        show_main_window(app.clone())
+    }
+}
+
+/// Rebinds the global shortcut that toggles the launcher and saves it to the
+/// settings. The previous shortcut stays active if the new one is invalid or
+/// already taken.
+#[tauri::command]
+pub fn set_hotkey(
+    app: AppHandle,
+    hotkey: String,
+    settings: State<'_, SettingsStore>,
+) -> Result<(), String> {
+    let mut updated = settings.get();
+    updated.hotkey = hotkey.trim().to_string();
+    apply_settings(&app, updated, true).map(|_| ())
+}
+
+/// Returns the settings currently in effect
+#[tauri::command]
+pub fn get_settings(settings: State<'_, SettingsStore>) -> Settings {
+    settings.get()
+}
+
+/// Why the settings file isn't in effect, if it failed to load. The
+/// `settings-error` sent at startup can come before the UI listens.
+#[tauri::command]
+pub fn get_settings_error(settings: State<'_, SettingsStore>) -> Option<String> {
+    settings.error()
+}
+
+/// Validates, saves and applies new settings, then emits `settings-changed`.
+/// Nothing changes if validation fails or the new shortcut can't be bound.
+/// `usage_file` can only be changed by editing the settings file, since the
+/// usage store is rewritten wherever it points.
 #[tauri::command]
-pub async fn read_icon_file(path: String) -> Result<Vec<u8>, String> {
-    fs::read(&path).map_err(|e| format!("Failed to read icon file: {}", e))
+pub fn update_settings(
+    app: AppHandle,
+    settings: Settings,
+    current: State<'_, SettingsStore>,
+) -> Result<Settings, String> {
+    if settings.usage_file != current.get().usage_file {
+        return Err("The usage file can only be changed in the settings file".to_string());
+    }
+    apply_settings(&app, settings, true)
+}
+
+/// Makes `settings` current and applies them to the running app. `persist`
+/// is false for settings that were just read from the file, so hand-edited
+/// files are never rewritten.
+pub(crate) fn apply_settings(
+    app: &AppHandle,
+    settings: Settings,
+    persist: bool,
+) -> Result<Settings, String> {
+    settings.validate()?;
+
+    let store = app.state::<SettingsStore>();
+    let previous = store.get();
+    let hotkey_changed = settings.hotkey != previous.hotkey;
+
+    if hotkey_changed {
+        hotkey::bind(app, &settings.hotkey)?;
+    }
+    if !persist {
+        store.set(settings.clone());
+    } else if let Err(e) = store.replace(settings.clone()) {
+        if hotkey_changed {
+            let _ = hotkey::bind(app, &previous.hotkey);
+        }
+        return Err(e);
+    }
+
+    if let Some(window) = app.get_webview_window("main") {
+        if let Err(e) = window.set_always_on_top(settings.always_on_top) {
+            eprintln!("Failed to update always-on-top: {}", e);
+        }
+    }
+    if settings.discovery != previous.discovery {
+        spawn_rescan(app.clone());
+    }
+
+    let _ = app.emit("settings-changed", &settings);
+    Ok(settings)
+}
+
+/// Reads an icon file. Only icon paths reported by discovery are served.
+#[tauri::command]
+pub async fn read_icon_file(
+    path: String,
+    index: State<'_, AppIndex>,
+) -> Result<Vec<u8>, CommandError> {
+    ensure_known_icon(&index, &path)?;
+    Ok(fs::read(&path).map_err(|e| format!("Failed to read icon file: {}", e))?)
 }
 
+/// Returns an icon as base64 PNG. Only icon paths reported by discovery are served.
 #[tauri::command]
-pub async fn get_app_icon_base64(icon_path: String) -> Result<String, String> {
+pub async fn get_app_icon_base64(
+    icon_path: String,
+    index: State<'_, AppIndex>,
+) -> Result<String, CommandError> {
+    ensure_known_icon(&index, &icon_path)?;
+    Ok(encode_icon_png(&icon_path)?)
+}
+
+fn ensure_known_icon(index: &AppIndex, icon_path: &str) -> Result<(), CommandError> {
+    if index.is_known_icon(icon_path) {
+        Ok(())
+    } else {
+        Err(CommandError::NotAllowed(format!(
+            "'{}' is not an icon of an indexed app",
+            icon_path
+        )))
+    }
+}
+
+fn encode_icon_png(icon_path: &str) -> Result<String, String> {
     // First try to read the file
-    let icon_data = fs::read(&icon_path).map_err(|e| format!("Failed to read icon file: {}", e))?;
+    let icon_data = fs::read(icon_path).map_err(|e| format!("Failed to read icon file: {}", e))?;
     
     // Try to convert ICNS to PNG using the image crate
     match image::load_from_memory(&icon_data) {
@@ -332,21 +948,21 @@ pub async fn get_app_icon_base64(icon_path: String) -> Result<String, String> {
             img.write_to(&mut cursor, ImageFormat::Png)
                 .map_err(|e| format!("Failed to convert image: {}", e))?;
             
-            Ok(base64::encode(&png_data))
+            Ok(STANDARD.encode(&png_data))
         }
         Err(e) => {
             // If image crate fails, try alternative approach
-            println!("Image crate failed: {}, trying alternative", e);
+            eprintln!("Image crate failed: {}, trying alternative", e);
             
             #[cfg(target_os = "macos")]
             {
                 // Use sips command as fallback for macOS
-                convert_icns_with_sips(&icon_path)
+                convert_icns_with_sips(icon_path)
             }
             #[cfg(not(target_os = "macos"))]
             {
                 // Just return the raw icon data as base64
-                Ok(base64::encode(&icon_data))
+                Ok(STANDARD.encode(&icon_data))
             }
         }
     }
@@ -381,5 +997,5 @@ fn convert_icns_with_sips(icon_path: &str) -> Result<String, String> {
     // Clean up temp file
     let _ = fs::remove_file(&temp_png);
     
-    Ok(base64::encode(&png_data))
+    Ok(STANDARD.encode(&png_data))
 }
\ No newline at end of file
@@ -0,0 +1,65 @@
+use crate::cmd;
+use crate::settings::{self, SettingsStore};
+use std::fs;
+use std::thread;
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// How often the settings file is checked for outside edits
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Applies edits made to the settings file outside the app. A file that
+/// fails to parse or validate, at startup or later, is reported as
+/// `settings-error` and the settings in effect are kept until it is fixed.
+pub fn watch_settings(app: AppHandle) {
+    thread::spawn(move || {
+        let store = app.state::<SettingsStore>();
+        if let Some(e) = store.error() {
+            let _ = app.emit("settings-error", e);
+        }
+        let mut last_seen = file_stamp();
+
+        loop {
+            thread::sleep(POLL_INTERVAL);
+
+            let stamp = file_stamp();
+            if stamp == last_seen {
+                continue;
+            }
+            last_seen = stamp;
+
+            let failing = store.error().is_some();
+            match reload(&app) {
+                Ok(()) => {
+                    store.set_error(None);
+                    if failing {
+                        // A fix that matches the settings in effect changes
+                        // nothing, but the UI still needs to drop the error
+                        let _ = app.emit("settings-changed", store.get());
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Keeping previous settings: {}", e);
+                    store.set_error(Some(e.clone()));
+                    let _ = app.emit("settings-error", e);
+                }
+            }
+        }
+    });
+}
+
+/// Modification time and size, so edits within the same second still count
+fn file_stamp() -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(settings::settings_file().ok()?).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+fn reload(app: &AppHandle) -> Result<(), String> {
+    let settings = settings::load_settings()?;
+
+    // Our own saves land here too and are already in effect
+    if settings == app.state::<SettingsStore>().get() {
+        return Ok(());
+    }
+    cmd::apply_settings(app, settings, false).map(|_| ())
+}
@@ -0,0 +1,314 @@
+use crate::cmd;
+use crate::error::CommandError;
+use crate::single_instance::{self, Request};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+/// Protocol versions this build speaks, newest first. Every method and
+/// response currently has the same shape in all of them, so `negotiate` only
+/// tells clients which version they are talking.
+const PROTOCOL_VERSIONS: &[u32] = &[1];
+
+/// Connections idle for this long are closed
+const READ_TIMEOUT: Duration = Duration::from_secs(60);
+
+const METHODS: &[&str] = &[
+    "negotiate",
+    "list_apps",
+    "search_apps",
+    "open_app",
+    "show",
+    "hide",
+];
+
+// Standard JSON-RPC 2.0 error codes
+const PARSE_ERROR: i32 = -32700;
+const INVALID_REQUEST: i32 = -32600;
+const METHOD_NOT_FOUND: i32 = -32601;
+const INVALID_PARAMS: i32 = -32602;
+
+// Application error codes
+const FAILED: i32 = -32000;
+const NOT_ALLOWED: i32 = -32001;
+const UNSUPPORTED_VERSION: i32 = -32002;
+
+#[derive(Deserialize)]
+struct RpcRequest {
+    jsonrpc: String,
+    /// Absent for notifications, which get no response
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Serialize)]
+struct RpcResponse {
+    jsonrpc: &'static str,
+    id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<RpcError>,
+}
+
+#[derive(Serialize, Debug)]
+struct RpcError {
+    code: i32,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i32, message: impl Into<String>) -> Self {
+        RpcError {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<String> for RpcError {
+    fn from(message: String) -> Self {
+        RpcError::new(FAILED, message)
+    }
+}
+
+impl From<CommandError> for RpcError {
+    fn from(error: CommandError) -> Self {
+        match error {
+            CommandError::NotAllowed(message) => RpcError::new(NOT_ALLOWED, message),
+            CommandError::Failed(message) => RpcError::new(FAILED, message),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct NegotiateParams {
+    versions: Vec<u32>,
+}
+
+#[derive(Deserialize)]
+struct SearchParams {
+    query: String,
+}
+
+#[derive(Deserialize)]
+struct OpenParams {
+    bundle_id: String,
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(default)]
+    focus_existing: Option<bool>,
+}
+
+pub fn socket_path() -> Result<PathBuf, String> {
+    single_instance::socket_file("control.sock")
+}
+
+/// Serves JSON-RPC 2.0 on a Unix socket only the current user can reach.
+/// Each line is one request object with named params; each response is
+/// written back as one line. Methods mirror the Tauri commands and act on
+/// the same state.
+pub fn listen(app: AppHandle) -> Result<(), String> {
+    let listener = single_instance::bind_private(&socket_path()?)?;
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let app = app.clone();
+                    thread::spawn(move || serve(&app, stream));
+                }
+                Err(e) => eprintln!("Failed to accept control connection: {}", e),
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Removes the control socket on shutdown
+pub fn cleanup() {
+    if let Ok(socket_path) = socket_path() {
+        let _ = std::fs::remove_file(socket_path);
+    }
+}
+
+fn serve(app: &AppHandle, stream: UnixStream) {
+    if let Err(e) = stream
+        .set_read_timeout(Some(READ_TIMEOUT))
+        .and_then(|_| stream.set_write_timeout(Some(single_instance::SOCKET_TIMEOUT)))
+    {
+        eprintln!("Failed to set control connection timeout: {}", e);
+        return;
+    }
+
+    let mut writer = match stream.try_clone() {
+        Ok(writer) => writer,
+        Err(e) => {
+            eprintln!("Failed to open control connection: {}", e);
+            return;
+        }
+    };
+
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let Some(response) = handle_line(app, &line) else {
+            continue;
+        };
+        let Ok(json) = serde_json::to_string(&response) else {
+            continue;
+        };
+        if writeln!(writer, "{}", json).is_err() {
+            break;
+        }
+    }
+}
+
+/// Runs one request and returns its response, or None for a notification
+fn handle_line(app: &AppHandle, line: &str) -> Option<RpcResponse> {
+    let value: Value = match serde_json::from_str(line) {
+        Ok(value) => value,
+        Err(e) => {
+            return Some(error_response(
+                Value::Null,
+                RpcError::new(PARSE_ERROR, format!("Parse error: {}", e)),
+            ))
+        }
+    };
+    if value.is_array() {
+        return Some(error_response(
+            Value::Null,
+            RpcError::new(INVALID_REQUEST, "Batch requests are not supported"),
+        ));
+    }
+
+    let request: RpcRequest = match serde_json::from_value(value) {
+        Ok(request) => request,
+        Err(e) => {
+            return Some(error_response(
+                Value::Null,
+                RpcError::new(INVALID_REQUEST, format!("Invalid request: {}", e)),
+            ))
+        }
+    };
+    if request.jsonrpc != "2.0" {
+        return Some(error_response(
+            request.id.unwrap_or(Value::Null),
+            RpcError::new(INVALID_REQUEST, "Only JSON-RPC 2.0 is supported"),
+        ));
+    }
+
+    let started = Instant::now();
+    let result = dispatch(app, &request.method, request.params);
+
+    let elapsed = started.elapsed().as_millis();
+    match &result {
+        Ok(_) => eprintln!("control: {} ok ({} ms)", request.method, elapsed),
+        Err(e) => eprintln!(
+            "control: {} failed with {} ({} ms): {}",
+            request.method, e.code, elapsed, e.message
+        ),
+    }
+
+    let id = request.id?;
+    Some(match result {
+        Ok(result) => RpcResponse {
+            jsonrpc: "2.0",
+            id,
+            result: Some(result),
+            error: None,
+        },
+        Err(error) => error_response(id, error),
+    })
+}
+
+fn error_response(id: Value, error: RpcError) -> RpcResponse {
+    RpcResponse {
+        jsonrpc: "2.0",
+        id,
+        result: None,
+        error: Some(error),
+    }
+}
+
+fn dispatch(app: &AppHandle, method: &str, params: Value) -> Result<Value, RpcError> {
+    use tauri::async_runtime::block_on;
+
+    match method {
+        "negotiate" => {
+            let params: NegotiateParams = parse_params(params)?;
+            let version = PROTOCOL_VERSIONS
+                .iter()
+                .copied()
+                .find(|version| params.versions.contains(version))
+                .ok_or_else(|| {
+                    RpcError::new(
+                        UNSUPPORTED_VERSION,
+                        format!(
+                            "None of the requested protocol versions are supported; supported: {:?}",
+                            PROTOCOL_VERSIONS
+                        ),
+                    )
+                })?;
+
+            Ok(json!({
+                "version": version,
+                "server": format!("relaunchpad {}", env!("CARGO_PKG_VERSION")),
+                "methods": METHODS,
+            }))
+        }
+        "list_apps" => to_result(block_on(cmd::list_apps(
+            app.clone(),
+            app.state(),
+            app.state(),
+        ))?),
+        "search_apps" => {
+            let params: SearchParams = parse_params(params)?;
+            to_result(block_on(cmd::search_apps(
+                params.query,
+                app.state(),
+                app.state(),
+                app.state(),
+            ))?)
+        }
+        "open_app" => {
+            let params: OpenParams = parse_params(params)?;
+            to_result(cmd::open_indexed(
+                app,
+                &params.bundle_id,
+                params.path,
+                params.focus_existing.unwrap_or(false),
+            )?)
+        }
+        "show" => to_result(single_instance::handle(app, Request::Show)?),
+        "hide" => to_result(single_instance::handle(app, Request::Hide)?),
+        _ => Err(RpcError::new(
+            METHOD_NOT_FOUND,
+            format!("Method '{}' not found", method),
+        )),
+    }
+}
+
+fn parse_params<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    serde_json::from_value(params)
+        .map_err(|e| RpcError::new(INVALID_PARAMS, format!("Invalid params: {}", e)))
+}
+
+fn to_result<T: Serialize>(value: T) -> Result<Value, RpcError> {
+    serde_json::to_value(value)
+        .map_err(|e| RpcError::new(FAILED, format!("Failed to serialize result: {}", e)))
+}
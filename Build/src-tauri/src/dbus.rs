@@ -0,0 +1,252 @@
+use crate::cmd;
+use crate::discovery::AppInfo;
+use crate::error::CommandError;
+use crate::index::IndexDiff;
+use crate::single_instance::{self, Request};
+use tauri::{AppHandle, Listener};
+use zbus::blocking::{connection, Connection};
+use zbus::object_server::SignalEmitter;
+use zbus::{fdo, interface};
+
+/// Well-known name owned on the session bus
+pub const BUS_NAME: &str = "com.nellowtcs.ReLaunchpad";
+
+pub const OBJECT_PATH: &str = "/com/nellowtcs/ReLaunchpad";
+
+/// What the bus methods act on: the running launcher, or a stand-in when
+/// the interface is served in tests
+pub trait Actions: Send + Sync + 'static {
+    fn handle(&self, request: Request) -> Result<(), String>;
+
+    /// Opens an indexed app, focusing it instead if it is already running
+    fn launch(&self, bundle_id: &str) -> Result<(), CommandError>;
+}
+
+impl Actions for AppHandle {
+    fn handle(&self, request: Request) -> Result<(), String> {
+        single_instance::handle(self, request)
+    }
+
+    fn launch(&self, bundle_id: &str) -> Result<(), CommandError> {
+        cmd::open_indexed(self, bundle_id, None, true).map(|_| ())
+    }
+}
+
+/// The launcher as seen from D-Bus, e.g. for desktop keybindings:
+/// `gdbus call --session --dest com.nellowtcs.ReLaunchpad
+/// --object-path /com/nellowtcs/ReLaunchpad --method com.nellowtcs.ReLaunchpad1.Toggle`
+struct Launcher {
+    actions: Box<dyn Actions>,
+}
+
+#[interface(name = "com.nellowtcs.ReLaunchpad1")]
+impl Launcher {
+    fn toggle(&self) -> fdo::Result<()> {
+        self.handle(Request::Toggle)
+    }
+
+    fn show(&self) -> fdo::Result<()> {
+        self.handle(Request::Show)
+    }
+
+    fn hide(&self) -> fdo::Result<()> {
+        self.handle(Request::Hide)
+    }
+
+    /// Shows the launcher with `query` in the search field
+    fn search(&self, query: String) -> fdo::Result<()> {
+        self.handle(Request::Search(query))
+    }
+
+    /// Opens an indexed app, focusing it instead if it is already running.
+    /// The launch is counted like any other.
+    fn launch(&self, id: String) -> fdo::Result<()> {
+        self.actions.launch(&id).map_err(|e| match e {
+            CommandError::NotAllowed(message) => fdo::Error::AccessDenied(message),
+            CommandError::Failed(message) => fdo::Error::Failed(message),
+        })
+    }
+
+    /// Bundle ids added, removed and updated by a rescan
+    #[zbus(signal)]
+    async fn apps_changed(
+        emitter: &SignalEmitter<'_>,
+        added: Vec<String>,
+        removed: Vec<String>,
+        updated: Vec<String>,
+    ) -> zbus::Result<()>;
+}
+
+impl Launcher {
+    fn handle(&self, request: Request) -> fdo::Result<()> {
+        self.actions.handle(request).map_err(fdo::Error::Failed)
+    }
+}
+
+/// Owns `BUS_NAME` on the session bus and relays `apps-changed` as the
+/// `AppsChanged` signal. The bus comes from `DBUS_SESSION_BUS_ADDRESS`, so
+/// pointing it at a private `dbus-daemon --session` keeps tests off the
+/// desktop's bus.
+pub fn serve(app: AppHandle) -> Result<(), String> {
+    let connection = connection::Builder::session()
+        .and_then(|builder| register(builder, Box::new(app.clone())))
+        .map_err(|e| format!("Failed to register {} on the session bus: {}", BUS_NAME, e))?;
+
+    // The listener keeps the connection, and with it the bus name, alive
+    app.listen("apps-changed", move |event| {
+        let diff: IndexDiff = match serde_json::from_str(event.payload()) {
+            Ok(diff) => diff,
+            Err(e) => {
+                eprintln!("Ignoring malformed apps-changed event: {}", e);
+                return;
+            }
+        };
+        let connection = connection.inner().clone();
+
+        // Listeners run on the emitting thread, which may be inside the runtime
+        tauri::async_runtime::spawn(async move {
+            let emitted = match SignalEmitter::new(&connection, OBJECT_PATH) {
+                Ok(emitter) => {
+                    Launcher::apps_changed(
+                        &emitter,
+                        bundle_ids(diff.added),
+                        diff.removed,
+                        bundle_ids(diff.updated),
+                    )
+                    .await
+                }
+                Err(e) => Err(e),
+            };
+            if let Err(e) = emitted {
+                eprintln!("Failed to emit AppsChanged: {}", e);
+            }
+        });
+    });
+
+    Ok(())
+}
+
+/// Claims `BUS_NAME` and serves the interface on the bus `builder` connects to
+fn register(
+    builder: connection::Builder<'_>,
+    actions: Box<dyn Actions>,
+) -> zbus::Result<Connection> {
+    builder
+        .name(BUS_NAME)?
+        .serve_at(OBJECT_PATH, Launcher { actions })?
+        .build()
+}
+
+fn bundle_ids(apps: Vec<AppInfo>) -> Vec<String> {
+    apps.into_iter().map(|app| app.bundle_id).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::process::{Child, Command, Stdio};
+    use std::sync::{Arc, Mutex};
+
+    const INTERFACE: &str = "com.nellowtcs.ReLaunchpad1";
+
+    /// Records what the bus asked for; only `org.example.App` is indexed
+    #[derive(Clone, Default)]
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    impl Actions for Recorder {
+        fn handle(&self, request: Request) -> Result<(), String> {
+            self.0.lock().unwrap().push(format!("{:?}", request));
+            Ok(())
+        }
+
+        fn launch(&self, bundle_id: &str) -> Result<(), CommandError> {
+            if bundle_id != "org.example.App" {
+                return Err(CommandError::NotAllowed(format!(
+                    "'{}' is not an indexed app",
+                    bundle_id
+                )));
+            }
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("Launch({})", bundle_id));
+            Ok(())
+        }
+    }
+
+    /// A private session bus, stopped when dropped
+    struct Bus {
+        daemon: Child,
+        address: String,
+    }
+
+    impl Bus {
+        /// None when `dbus-daemon` isn't installed
+        fn start() -> Option<Self> {
+            let mut daemon = Command::new("dbus-daemon")
+                .args(["--session", "--nofork", "--print-address"])
+                .stdout(Stdio::piped())
+                .spawn()
+                .ok()?;
+
+            let mut address = String::new();
+            BufReader::new(daemon.stdout.take()?)
+                .read_line(&mut address)
+                .ok()?;
+            Some(Bus {
+                daemon,
+                address: address.trim().to_string(),
+            })
+        }
+    }
+
+    impl Drop for Bus {
+        fn drop(&mut self) {
+            let _ = self.daemon.kill();
+            let _ = self.daemon.wait();
+        }
+    }
+
+    #[test]
+    fn serves_the_interface_on_the_bus() {
+        let Some(bus) = Bus::start() else {
+            eprintln!("dbus-daemon is not installed, skipping");
+            return;
+        };
+        let recorder = Recorder::default();
+
+        let _server = connection::Builder::address(bus.address.as_str())
+            .and_then(|builder| register(builder, Box::new(recorder.clone())))
+            .unwrap();
+        let client = connection::Builder::address(bus.address.as_str())
+            .and_then(|builder| builder.build())
+            .unwrap();
+        let call = |method: &str, body: &str| {
+            client.call_method(Some(BUS_NAME), OBJECT_PATH, Some(INTERFACE), method, &body)
+        };
+
+        client
+            .call_method(Some(BUS_NAME), OBJECT_PATH, Some(INTERFACE), "Toggle", &())
+            .unwrap();
+        call("Search", "fire").unwrap();
+        call("Launch", "org.example.App").unwrap();
+
+        let refused = call("Launch", "org.example.Missing").unwrap_err();
+        match refused {
+            zbus::Error::MethodError(name, _, _) => {
+                assert_eq!(name.as_str(), "org.freedesktop.DBus.Error.AccessDenied")
+            }
+            other => panic!("unexpected error: {}", other),
+        }
+
+        assert_eq!(
+            *recorder.0.lock().unwrap(),
+            vec![
+                "Toggle".to_string(),
+                "Search(\"fire\")".to_string(),
+                "Launch(org.example.App)".to_string(),
+            ]
+        );
+    }
+}
@@ -0,0 +1,316 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The parts of a FreeDesktop `.desktop` file needed to launch it
+#[derive(Debug, Clone)]
+pub struct DesktopEntry {
+    pub path: PathBuf,
+    pub name: String,
+    pub exec: String,
+    pub icon: Option<String>,
+}
+
+impl DesktopEntry {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let contents =
+            fs::read_to_string(path).map_err(|e| format!("Failed to read desktop entry: {}", e))?;
+        parse_desktop_entry(path, &contents)
+            .ok_or_else(|| format!("'{}' is not a launchable desktop entry", path.display()))
+    }
+}
+
+/// Finds `<id>.desktop` in the XDG application directories
+pub fn find_desktop_entry(id: &str) -> Option<PathBuf> {
+    let file_name = format!("{}.desktop", id);
+    application_dirs()
+        .into_iter()
+        .map(|dir| dir.join(&file_name))
+        .find(|path| path.is_file())
+}
+
+/// `$XDG_DATA_HOME/applications` followed by each `$XDG_DATA_DIRS` entry
+fn application_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+
+    match std::env::var("XDG_DATA_HOME")
+        .ok()
+        .filter(|d| !d.is_empty())
+    {
+        Some(data_home) => dirs.push(PathBuf::from(data_home)),
+        None => {
+            if let Ok(home) = std::env::var("HOME") {
+                dirs.push(Path::new(&home).join(".local/share"));
+            }
+        }
+    }
+
+    let data_dirs = std::env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|d| !d.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+    dirs.extend(data_dirs.split(':').map(PathBuf::from));
+
+    dirs.into_iter()
+        .map(|dir| dir.join("applications"))
+        .collect()
+}
+
+pub fn parse_desktop_entry(path: &Path, contents: &str) -> Option<DesktopEntry> {
+    let mut in_main_group = false;
+    let mut name = None;
+    let mut exec = None;
+    let mut icon = None;
+
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with('[') {
+            in_main_group = line == "[Desktop Entry]";
+            continue;
+        }
+        if !in_main_group {
+            continue;
+        }
+
+        // Localized keys such as `Name[de]` are ignored
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        match key.trim() {
+            "Name" => name = Some(value.trim().to_string()),
+            "Exec" => exec = Some(value.trim().to_string()),
+            "Icon" => icon = Some(value.trim().to_string()),
+            _ => {}
+        }
+    }
+
+    Some(DesktopEntry {
+        path: path.to_path_buf(),
+        name: name?,
+        exec: exec?,
+        icon,
+    })
+}
+
+/// Expands the `Exec` field codes for the given targets, returning one argv
+/// per process to start. Single-target codes (`%f`, `%u`) start one process
+/// per target; list codes (`%F`, `%U`) pass every target to one process.
+pub fn expand_exec(entry: &DesktopEntry, targets: &[String]) -> Result<Vec<Vec<String>>, String> {
+    let tokens = split_exec(&entry.exec)?;
+    if tokens.is_empty() {
+        return Err("Desktop entry has an empty Exec line".to_string());
+    }
+
+    let single_target = tokens.iter().any(|t| t == "%f" || t == "%u");
+    let takes_targets = single_target || tokens.iter().any(|t| t == "%F" || t == "%U");
+
+    let batches: Vec<&[String]> = if single_target && targets.len() > 1 {
+        targets.chunks(1).collect()
+    } else {
+        vec![targets]
+    };
+
+    Ok(batches
+        .into_iter()
+        .map(|batch| {
+            let mut argv = Vec::new();
+            for token in &tokens {
+                match token.as_str() {
+                    "%f" | "%u" | "%F" | "%U" => argv.extend(batch.iter().cloned()),
+                    "%i" => {
+                        if let Some(icon) = &entry.icon {
+                            argv.push("--icon".to_string());
+                            argv.push(icon.clone());
+                        }
+                    }
+                    "%c" => argv.push(entry.name.clone()),
+                    "%k" => argv.push(entry.path.to_string_lossy().into_owned()),
+                    // Deprecated codes are dropped, as the spec asks
+                    "%d" | "%D" | "%n" | "%N" | "%v" | "%m" => {}
+                    _ => argv.push(expand_inline_codes(token, entry)),
+                }
+            }
+
+            // Entries without a file code still get the targets appended
+            if !takes_targets {
+                argv.extend(batch.iter().cloned());
+            }
+            argv
+        })
+        .collect())
+}
+
+/// Handles codes embedded inside a larger argument, e.g. `--name=%c`
+fn expand_inline_codes(token: &str, entry: &DesktopEntry) -> String {
+    let mut expanded = String::new();
+    let mut chars = token.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            expanded.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('%') => expanded.push('%'),
+            Some('c') => expanded.push_str(&entry.name),
+            Some('k') => expanded.push_str(&entry.path.to_string_lossy()),
+            Some(_) | None => {}
+        }
+    }
+
+    expanded
+}
+
+/// Splits an `Exec` value into arguments, honouring double quotes and the
+/// backslash escapes the spec allows inside them
+fn split_exec(exec: &str) -> Result<Vec<String>, String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_token = false;
+    let mut chars = exec.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                in_token = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(escaped) => current.push(escaped),
+                            None => return Err("Unterminated escape in Exec".to_string()),
+                        },
+                        Some(c) => current.push(c),
+                        None => return Err("Unterminated quote in Exec".to_string()),
+                    }
+                }
+            }
+            ' ' | '\t' => {
+                if in_token {
+                    tokens.push(std::mem::take(&mut current));
+                    in_token = false;
+                }
+            }
+            c => {
+                in_token = true;
+                current.push(c);
+            }
+        }
+    }
+
+    if in_token {
+        tokens.push(current);
+    }
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(exec: &str) -> DesktopEntry {
+        DesktopEntry {
+            path: PathBuf::from("/usr/share/applications/editor.desktop"),
+            name: "Editor".to_string(),
+            exec: exec.to_string(),
+            icon: Some("editor".to_string()),
+        }
+    }
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    fn expand(exec: &str, targets: &[&str]) -> Vec<Vec<String>> {
+        expand_exec(&entry(exec), &strings(targets)).unwrap()
+    }
+
+    #[test]
+    fn single_target_codes_start_one_process_per_target() {
+        assert_eq!(
+            expand("editor %f", &["a.txt", "b.txt"]),
+            vec![strings(&["editor", "a.txt"]), strings(&["editor", "b.txt"])]
+        );
+        assert_eq!(
+            expand("browser --new %u", &["https://example.com"]),
+            vec![strings(&["browser", "--new", "https://example.com"])]
+        );
+        assert_eq!(expand("editor %f", &[]), vec![strings(&["editor"])]);
+    }
+
+    #[test]
+    fn list_codes_pass_every_target_to_one_process() {
+        assert_eq!(
+            expand("editor %F --wait", &["a.txt", "b.txt"]),
+            vec![strings(&["editor", "a.txt", "b.txt", "--wait"])]
+        );
+        assert_eq!(
+            expand("browser %U", &["https://a.example", "https://b.example"]),
+            vec![strings(&[
+                "browser",
+                "https://a.example",
+                "https://b.example"
+            ])]
+        );
+    }
+
+    #[test]
+    fn appends_targets_when_there_is_no_file_code() {
+        assert_eq!(
+            expand("editor --new-window", &["a.txt"]),
+            vec![strings(&["editor", "--new-window", "a.txt"])]
+        );
+    }
+
+    #[test]
+    fn expands_entry_codes_and_drops_deprecated_ones() {
+        assert_eq!(
+            expand("editor %i %c %k %d %D %n %N %v %m %f", &["a.txt"]),
+            vec![strings(&[
+                "editor",
+                "--icon",
+                "editor",
+                "Editor",
+                "/usr/share/applications/editor.desktop",
+                "a.txt",
+            ])]
+        );
+        assert_eq!(
+            expand("editor --title=%c --progress=100%% %%", &[]),
+            vec![strings(&[
+                "editor",
+                "--title=Editor",
+                "--progress=100%",
+                "%"
+            ])]
+        );
+    }
+
+    #[test]
+    fn splits_quoted_arguments_and_escapes() {
+        assert_eq!(
+            split_exec(
+                r#""/opt/My Editor/editor"   --flag	"say \"hi\"" "back\\slash" "\$HOME" """#
+            )
+            .unwrap(),
+            strings(&[
+                "/opt/My Editor/editor",
+                "--flag",
+                "say \"hi\"",
+                "back\\slash",
+                "$HOME",
+                "",
+            ])
+        );
+        assert_eq!(split_exec("a\"b c\"d").unwrap(), strings(&["ab cd"]));
+    }
+
+    #[test]
+    fn rejects_broken_exec_lines() {
+        assert!(split_exec("editor \"unterminated").is_err());
+        assert!(split_exec("editor \"dangling\\").is_err());
+        assert!(expand_exec(&entry("   "), &[]).is_err());
+    }
+}
@@ -0,0 +1,672 @@
+use plist::Value;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering as CmpOrdering;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+/// Number of parsed bundles collected before a progress batch is reported
+const PROGRESS_BATCH_SIZE: usize = 24;
+
+/// Roots searched by default, in both Spotlight and the directory walk
+pub const DEFAULT_ROOTS: &[&str] = &["/Applications", "/System/Applications", "~/Applications"];
+
+/// How many directory levels below a root bundles are looked for by default
+pub const DEFAULT_ROOT_DEPTH: usize = 3;
+
+/// Paths skipped by default: mounted images, backups and trashed apps
+pub const DEFAULT_EXCLUDES: &[&str] = &[
+    "/Volumes",
+    "**/Backups.backupdb",
+    "**/.Trash",
+    "**/DerivedData",
+];
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AppInfo {
+    pub name: String,
+    pub bundle_id: String,
+    pub path: String,
+    pub icon_path: Option<String>,
+    /// Changes whenever the icon file does, so the UI can cache decoded icons
+    #[serde(default)]
+    pub icon_key: Option<String>,
+    pub category: Option<String>,
+    pub version: Option<String>,
+    /// Other installed copies of the same bundle, best candidate first
+    #[serde(default)]
+    pub alternates: Vec<AppCopy>,
+    /// Set when the app is returned to the UI; never stored in the index
+    #[serde(default)]
+    pub running: bool,
+}
+
+/// A non-canonical copy of an app, e.g. a second install in `~/Downloads`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AppCopy {
+    pub path: String,
+    pub version: Option<String>,
+}
+
+impl AppInfo {
+    /// Whether `path` is the canonical copy or one of the alternates
+    pub fn has_copy_at(&self, path: &str) -> bool {
+        self.path == path || self.alternates.iter().any(|copy| copy.path == path)
+    }
+}
+
+/// A batch of freshly parsed apps, reported while discovery is still running
+#[derive(Serialize, Debug, Clone)]
+pub struct DiscoveryProgress {
+    pub apps: Vec<AppInfo>,
+    pub scanned: usize,
+    pub total: usize,
+}
+
+/// A folder searched for bundles, and how deep below it to look
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DiscoveryRoot {
+    pub path: String,
+    pub max_depth: usize,
+}
+
+/// Which parts of the filesystem discovery is allowed to report apps from.
+/// Exclusions are path prefixes or globs (`*`, `?` and `**`); a bundle is
+/// skipped when it or any of its parent folders matches one.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct DiscoveryConfig {
+    pub roots: Vec<DiscoveryRoot>,
+    pub exclude: Vec<String>,
+}
+
+impl Default for DiscoveryConfig {
+    fn default() -> Self {
+        DiscoveryConfig {
+            roots: DEFAULT_ROOTS
+                .iter()
+                .map(|root| DiscoveryRoot {
+                    path: root.to_string(),
+                    max_depth: DEFAULT_ROOT_DEPTH,
+                })
+                .collect(),
+            exclude: DEFAULT_EXCLUDES.iter().map(|e| e.to_string()).collect(),
+        }
+    }
+}
+
+impl DiscoveryConfig {
+    /// Whether a bundle path lies under a root, within that root's depth, and
+    /// outside every exclusion
+    pub fn allows(&self, bundle_path: &Path) -> bool {
+        let in_root = self.roots.iter().any(|root| {
+            bundle_path
+                .strip_prefix(expand_home(&root.path))
+                .is_ok_and(|rest| rest.components().count() <= root.max_depth)
+        });
+
+        in_root && !self.is_excluded(bundle_path)
+    }
+
+    /// Position of the first root containing `path`; earlier roots win when
+    /// choosing between copies of the same app
+    fn root_priority(&self, path: &Path) -> usize {
+        self.roots
+            .iter()
+            .position(|root| path.starts_with(expand_home(&root.path)))
+            .unwrap_or(self.roots.len())
+    }
+
+    pub fn is_excluded(&self, path: &Path) -> bool {
+        self.exclude
+            .iter()
+            .any(|pattern| matches_exclusion(&expand_home(pattern), path))
+    }
+}
+
+/// Finds every application bundle, preferring Spotlight and falling back to
+/// walking the configured roots when Spotlight fails or has indexing disabled
+pub fn find_bundle_paths(config: &DiscoveryConfig) -> Result<Vec<String>, String> {
+    match spotlight_bundle_paths(config) {
+        Ok(paths) if !paths.is_empty() => return Ok(paths),
+        Ok(_) => eprintln!("Spotlight returned no apps, walking application folders"),
+        Err(e) => eprintln!("{}, walking application folders", e),
+    }
+
+    let paths = walk_bundle_paths(config);
+    if paths.is_empty() {
+        return Err("No application bundles found".to_string());
+    }
+
+    Ok(paths)
+}
+
+/// Asks Spotlight for the application bundles under the configured roots
+pub fn spotlight_bundle_paths(config: &DiscoveryConfig) -> Result<Vec<String>, String> {
+    let mut command = Command::new("mdfind");
+    for root in &config.roots {
+        let root = expand_home(&root.path);
+        if root.is_dir() {
+            command.arg("-onlyin").arg(root);
+        }
+    }
+
+    let output = command
+        .arg("kMDItemContentType == 'com.apple.application-bundle'")
+        .output()
+        .map_err(|e| format!("Failed to run mdfind: {}", e))?;
+
+    if !output.status.success() {
+        return Err("mdfind command failed".to_string());
+    }
+
+    let mut paths: Vec<String> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .filter(|path| !path.is_empty() && config.allows(Path::new(path)))
+        .map(str::to_string)
+        .collect();
+
+    // Overlapping roots make Spotlight report some bundles twice
+    paths.sort();
+    paths.dedup();
+    Ok(paths)
+}
+
+/// Walks the configured roots looking for `.app` bundles by their structure.
+/// Bundles are not descended into, and a bundle reached twice (through
+/// symlinks or overlapping roots) is only reported once.
+pub fn walk_bundle_paths(config: &DiscoveryConfig) -> Vec<String> {
+    let mut found = HashSet::new();
+    let mut bundles = Vec::new();
+
+    for root in &config.roots {
+        // Kept per root since roots have their own depth. A folder reached
+        // again is only walked again with more depth left than before.
+        let mut visited: HashMap<PathBuf, usize> = HashMap::new();
+        let mut pending = vec![(expand_home(&root.path), 0)];
+
+        while let Some((dir, depth)) = pending.pop() {
+            if config.is_excluded(&dir) {
+                continue;
+            }
+
+            // Canonical paths make symlink loops terminate
+            let Ok(canonical) = fs::canonicalize(&dir) else {
+                continue;
+            };
+
+            if is_app_bundle(&dir) {
+                if found.insert(canonical) {
+                    bundles.push(dir.to_string_lossy().into_owned());
+                }
+                continue;
+            }
+
+            if visited.get(&canonical).is_some_and(|seen| *seen <= depth) {
+                continue;
+            }
+            visited.insert(canonical, depth);
+
+            if depth >= root.max_depth {
+                continue;
+            }
+
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let path = entry.path();
+                // `metadata` follows symlinks, so linked folders are walked too
+                if fs::metadata(&path).map(|m| m.is_dir()).unwrap_or(false) {
+                    pending.push((path, depth + 1));
+                }
+            }
+        }
+    }
+
+    bundles.sort();
+    bundles
+}
+
+/// Collapses apps sharing a bundle id into one entry. The canonical copy is
+/// the one under the highest priority root, then the highest version; the
+/// rest are listed as alternates.
+pub fn group_by_bundle_id(apps: Vec<AppInfo>, config: &DiscoveryConfig) -> Vec<AppInfo> {
+    let mut groups: HashMap<String, Vec<AppInfo>> = HashMap::new();
+    for app in apps {
+        groups.entry(app.bundle_id.clone()).or_default().push(app);
+    }
+
+    groups
+        .into_values()
+        .map(|mut copies| {
+            copies.sort_by(|a, b| {
+                config
+                    .root_priority(Path::new(&a.path))
+                    .cmp(&config.root_priority(Path::new(&b.path)))
+                    .then_with(|| compare_versions(b.version.as_deref(), a.version.as_deref()))
+                    .then_with(|| a.path.cmp(&b.path))
+            });
+
+            let mut canonical = copies.remove(0);
+            canonical.alternates = copies
+                .into_iter()
+                .map(|copy| AppCopy {
+                    path: copy.path,
+                    version: copy.version,
+                })
+                .collect();
+            canonical
+        })
+        .collect()
+}
+
+/// Compares dotted version strings numerically, so "10.2" sorts after "9.14".
+/// A missing version sorts before any present one.
+fn compare_versions(a: Option<&str>, b: Option<&str>) -> CmpOrdering {
+    fn parts(version: &str) -> Vec<u64> {
+        version
+            .split(['.', '-', ' '])
+            .map(|part| {
+                let digits: String = part.chars().take_while(|c| c.is_ascii_digit()).collect();
+                digits.parse().unwrap_or(0)
+            })
+            .collect()
+    }
+
+    match (a, b) {
+        (Some(a), Some(b)) => parts(a).cmp(&parts(b)),
+        (a, b) => a.is_some().cmp(&b.is_some()),
+    }
+}
+
+/// A bundle is a `*.app` directory carrying a `Contents/Info.plist`
+fn is_app_bundle(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "app")
+        && path.join("Contents").join("Info.plist").is_file()
+}
+
+/// Expands a leading `~` to the user's home directory
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix('~'), std::env::var("HOME")) {
+        (Some(rest), Ok(home)) => PathBuf::from(format!("{}{}", home, rest)),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// True when `path`, or any folder above it, matches `pattern`
+fn matches_exclusion(pattern: &Path, path: &Path) -> bool {
+    let pattern: Vec<String> = pattern
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    let path: Vec<String> = path
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect();
+
+    (1..=path.len()).any(|len| glob_segments(&pattern, &path[..len]))
+}
+
+fn glob_segments(pattern: &[String], path: &[String]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((first, rest)) if first == "**" => {
+            glob_segments(rest, path) || (!path.is_empty() && glob_segments(pattern, &path[1..]))
+        }
+        Some((first, rest)) => match path.split_first() {
+            Some((segment, path_rest)) => {
+                glob_segment(first.as_bytes(), segment.as_bytes()) && glob_segments(rest, path_rest)
+            }
+            None => false,
+        },
+    }
+}
+
+/// Matches a single path segment against `*` and `?` wildcards
+fn glob_segment(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some((b'*', rest)) => (0..=text.len()).any(|skip| glob_segment(rest, &text[skip..])),
+        Some((b'?', rest)) => !text.is_empty() && glob_segment(rest, &text[1..]),
+        Some((c, rest)) => text.first() == Some(c) && glob_segment(rest, &text[1..]),
+    }
+}
+
+/// Parses bundles in parallel, calling `on_progress` as batches complete.
+/// Results are returned in no particular order.
+pub fn parse_bundles<F>(paths: &[String], on_progress: F) -> Vec<AppInfo>
+where
+    F: Fn(DiscoveryProgress) + Sync,
+{
+    let total = paths.len();
+    let next = AtomicUsize::new(0);
+    let scanned = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(total));
+
+    let workers = thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(4)
+        .min(total.max(1));
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| {
+                let mut batch = Vec::with_capacity(PROGRESS_BATCH_SIZE);
+
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(path) = paths.get(index) else {
+                        break;
+                    };
+
+                    if let Some(app_info) = parse_app_info(path) {
+                        batch.push(app_info);
+                    }
+                    let done = scanned.fetch_add(1, Ordering::Relaxed) + 1;
+
+                    if batch.len() >= PROGRESS_BATCH_SIZE {
+                        flush_batch(&mut batch, &results, &on_progress, done, total);
+                    }
+                }
+
+                if !batch.is_empty() {
+                    let done = scanned.load(Ordering::Relaxed);
+                    flush_batch(&mut batch, &results, &on_progress, done, total);
+                }
+            });
+        }
+    });
+
+    results.into_inner().unwrap_or_else(|e| e.into_inner())
+}
+
+fn flush_batch<F>(
+    batch: &mut Vec<AppInfo>,
+    results: &Mutex<Vec<AppInfo>>,
+    on_progress: &F,
+    scanned: usize,
+    total: usize,
+) where
+    F: Fn(DiscoveryProgress),
+{
+    let apps = std::mem::take(batch);
+    results
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .extend(apps.iter().cloned());
+
+    on_progress(DiscoveryProgress {
+        apps,
+        scanned,
+        total,
+    });
+}
+
+pub fn parse_app_info(app_path: &str) -> Option<AppInfo> {
+    let plist_path = format!("{}/Contents/Info.plist", app_path);
+
+    // Check if the plist file exists
+    if !Path::new(&plist_path).exists() {
+        return None;
+    }
+
+    // Info.plist may be XML or binary, `from_file` handles both
+    let info = Value::from_file(&plist_path).ok()?;
+    let dict = info.as_dictionary()?;
+
+    // Get app name (prefer display name over bundle name)
+    let name = dict
+        .get("CFBundleDisplayName")
+        .or_else(|| dict.get("CFBundleName"))
+        .and_then(|v| v.as_string())
+        .filter(|s| !s.is_empty())
+        .unwrap_or("Unnamed")
+        .to_string();
+
+    // Get bundle identifier
+    let bundle_id = dict
+        .get("CFBundleIdentifier")
+        .and_then(|v| v.as_string())
+        .filter(|s| !s.is_empty())?
+        .to_string();
+
+    // Get version
+    let version = dict
+        .get("CFBundleShortVersionString")
+        .or_else(|| dict.get("CFBundleVersion"))
+        .and_then(|v| v.as_string())
+        .map(|s| s.to_string());
+
+    // Get category
+    let category = dict
+        .get("LSApplicationCategoryType")
+        .and_then(|v| v.as_string())
+        .map(format_app_category)
+        .or_else(|| guess_category_from_path(app_path));
+
+    // Get icon path
+    let icon_path = get_app_icon_path(dict, app_path);
+    let icon_key = icon_path.as_deref().and_then(icon_cache_key);
+
+    Some(AppInfo {
+        name,
+        bundle_id,
+        path: app_path.to_string(),
+        icon_path,
+        icon_key,
+        category,
+        version,
+        alternates: Vec::new(),
+        running: false,
+    })
+}
+
+/// Derives a cache key from the icon's path, size and modification time
+fn icon_cache_key(icon_path: &str) -> Option<String> {
+    let metadata = fs::metadata(icon_path).ok()?;
+    let modified = metadata
+        .modified()
+        .ok()?
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()?;
+
+    let mut hasher = DefaultHasher::new();
+    icon_path.hash(&mut hasher);
+    metadata.len().hash(&mut hasher);
+    modified.as_secs().hash(&mut hasher);
+    Some(format!("{:016x}", hasher.finish()))
+}
+
+fn format_app_category(category: &str) -> String {
+    match category {
+        "public.app-category.productivity" => "Productivity".to_string(),
+        "public.app-category.graphics-design" => "Graphics & Design".to_string(),
+        "public.app-category.developer-tools" => "Developer Tools".to_string(),
+        "public.app-category.entertainment" => "Entertainment".to_string(),
+        "public.app-category.education" => "Education".to_string(),
+        "public.app-category.lifestyle" => "Lifestyle".to_string(),
+        "public.app-category.utilities" => "Utilities".to_string(),
+        "public.app-category.games" => "Games".to_string(),
+        "public.app-category.social-networking" => "Social Networking".to_string(),
+        "public.app-category.finance" => "Finance".to_string(),
+        "public.app-category.photography" => "Photography".to_string(),
+        "public.app-category.music" => "Music".to_string(),
+        "public.app-category.video" => "Video".to_string(),
+        _ => "Other".to_string(),
+    }
+}
+
+fn guess_category_from_path(path: &str) -> Option<String> {
+    if path.contains("Utilities") {
+        Some("Utilities".to_string())
+    } else if path.contains("Games") {
+        Some("Games".to_string())
+    } else if path.contains("Graphics") || path.contains("Design") {
+        Some("Graphics & Design".to_string())
+    } else {
+        None
+    }
+}
+
+fn get_app_icon_path(dict: &plist::Dictionary, app_path: &str) -> Option<String> {
+    // Try different icon keys
+    let icon_keys = ["CFBundleIconFile", "CFBundleIconName", "CFBundleIcons"];
+
+    for key in icon_keys {
+        if let Some(icon_value) = dict.get(key) {
+            if let Some(icon_name) = icon_value.as_string() {
+                let mut icon_file = icon_name.to_string();
+
+                // Ensure .icns extension
+                if !icon_file.ends_with(".icns") {
+                    icon_file.push_str(".icns");
+                }
+
+                let icon_path = format!("{}/Contents/Resources/{}", app_path, icon_file);
+
+                // Check if the icon file actually exists
+                if Path::new(&icon_path).exists() {
+                    return Some(icon_path);
+                }
+            }
+        }
+    }
+
+    // Try to find any .icns file in Resources folder as fallback
+    let resources_path = format!("{}/Contents/Resources", app_path);
+    if let Ok(entries) = fs::read_dir(&resources_path) {
+        for entry in entries.flatten() {
+            if let Some(filename) = entry.file_name().to_str() {
+                if filename.ends_with(".icns") {
+                    return Some(format!("{}/{}", resources_path, filename));
+                }
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh directory under the system temp dir, removed when dropped
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "relaunchpad-discovery-{}-{}",
+                std::process::id(),
+                name
+            ));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            TempDir(path)
+        }
+
+        fn bundle(&self, relative: &str) -> String {
+            let path = self.0.join(relative);
+            fs::create_dir_all(path.join("Contents")).unwrap();
+            fs::write(path.join("Contents/Info.plist"), "").unwrap();
+            path.to_string_lossy().into_owned()
+        }
+
+        fn root(&self, relative: &str, max_depth: usize) -> DiscoveryRoot {
+            DiscoveryRoot {
+                path: self.0.join(relative).to_string_lossy().into_owned(),
+                max_depth,
+            }
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn walk(roots: Vec<DiscoveryRoot>, exclude: Vec<String>) -> Vec<String> {
+        walk_bundle_paths(&DiscoveryConfig { roots, exclude })
+    }
+
+    #[test]
+    fn stops_at_the_root_depth() {
+        let dir = TempDir::new("depth");
+        let top = dir.bundle("Top.app");
+        let nested = dir.bundle("a/Nested.app");
+        dir.bundle("a/b/Deep.app");
+
+        assert_eq!(walk(vec![dir.root("", 2)], vec![]), vec![top, nested]);
+    }
+
+    #[test]
+    fn does_not_descend_into_bundles() {
+        let dir = TempDir::new("bundles");
+        let outer = dir.bundle("Outer.app");
+        dir.bundle("Outer.app/Contents/Helpers/Helper.app");
+
+        assert_eq!(walk(vec![dir.root("", 5)], vec![]), vec![outer]);
+    }
+
+    #[test]
+    fn walks_into_app_folders_that_are_not_bundles() {
+        let dir = TempDir::new("not-bundles");
+        fs::create_dir_all(dir.0.join("Suite.app")).unwrap();
+        let inner = dir.bundle("Suite.app/Inner.app");
+
+        assert_eq!(walk(vec![dir.root("", 3)], vec![]), vec![inner]);
+    }
+
+    #[test]
+    fn skips_excluded_paths() {
+        let dir = TempDir::new("exclude");
+        let kept = dir.bundle("Kept.app");
+        dir.bundle("Skipped/Prefix.app");
+        dir.bundle("a/.Trash/Trashed.app");
+        let exclude = vec![
+            dir.0.join("Skipped").to_string_lossy().into_owned(),
+            "**/.Trash".to_string(),
+        ];
+
+        assert_eq!(walk(vec![dir.root("", 3)], exclude), vec![kept]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn terminates_on_symlink_loops() {
+        let dir = TempDir::new("loop");
+        let app = dir.bundle("a/App.app");
+        std::os::unix::fs::symlink(&dir.0, dir.0.join("a/loop")).unwrap();
+
+        assert_eq!(walk(vec![dir.root("", 10)], vec![]), vec![app]);
+    }
+
+    #[test]
+    fn each_root_walks_to_its_own_depth() {
+        let dir = TempDir::new("roots");
+        let deep = dir.bundle("shallow/deeper/App.app");
+
+        // The first root reaches `shallow` without room to look inside it
+        let roots = vec![dir.root("", 1), dir.root("shallow", 2)];
+        assert_eq!(walk(roots, vec![]), vec![deep]);
+    }
+
+    #[test]
+    fn reports_bundles_under_overlapping_roots_once() {
+        let dir = TempDir::new("overlap");
+        let app = dir.bundle("a/App.app");
+
+        let roots = vec![dir.root("", 3), dir.root("a", 3)];
+        assert_eq!(walk(roots, vec![]), vec![app]);
+    }
+}
@@ -0,0 +1,28 @@
+use serde::Serialize;
+use std::fmt;
+
+/// Error returned to the webview by commands that guard what they touch.
+/// Serialized as `{ "kind": "not_allowed" | "failed", "message": "..." }` so
+/// the UI can tell a refused request apart from one that went wrong.
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "kind", content = "message", rename_all = "snake_case")]
+pub enum CommandError {
+    /// The request named something discovery never produced
+    NotAllowed(String),
+    Failed(String),
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommandError::NotAllowed(message) => write!(f, "Not allowed: {}", message),
+            CommandError::Failed(message) => f.write_str(message),
+        }
+    }
+}
+
+impl From<String> for CommandError {
+    fn from(message: String) -> Self {
+        CommandError::Failed(message)
+    }
+}
@@ -0,0 +1,120 @@
+use crate::settings;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// Pinned apps, keyed by bundle id, in the order the user arranged them.
+/// Changes are saved before they replace the current list.
+pub struct FavoritesStore {
+    bundle_ids: Mutex<Vec<String>>,
+}
+
+impl FavoritesStore {
+    /// Loads the favorites file, starting empty if it is missing or broken
+    pub fn load() -> Self {
+        let bundle_ids = load_favorites().unwrap_or_else(|e| {
+            eprintln!("Starting without favorites: {}", e);
+            Vec::new()
+        });
+
+        FavoritesStore {
+            bundle_ids: Mutex::new(bundle_ids),
+        }
+    }
+
+    pub fn get(&self) -> Vec<String> {
+        self.bundle_ids
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Pins `bundle_id` at `position`, or last. An app that is already
+    /// pinned is moved there instead.
+    pub fn add(&self, bundle_id: &str, position: Option<usize>) -> Result<Vec<String>, String> {
+        self.update(|bundle_ids| {
+            bundle_ids.retain(|id| id != bundle_id);
+            let position = position.unwrap_or(bundle_ids.len()).min(bundle_ids.len());
+            bundle_ids.insert(position, bundle_id.to_string());
+            Ok(())
+        })
+    }
+
+    pub fn remove(&self, bundle_id: &str) -> Result<Vec<String>, String> {
+        self.update(|bundle_ids| {
+            bundle_ids.retain(|id| id != bundle_id);
+            Ok(())
+        })
+    }
+
+    /// Replaces the order; `order` must list exactly the current favorites
+    pub fn reorder(&self, order: Vec<String>) -> Result<Vec<String>, String> {
+        self.update(|bundle_ids| {
+            let mut current = bundle_ids.clone();
+            let mut requested = order.clone();
+            current.sort();
+            requested.sort();
+            if current != requested {
+                return Err("New order must list each favorite exactly once".to_string());
+            }
+
+            *bundle_ids = order;
+            Ok(())
+        })
+    }
+
+    /// Replaces the whole list, e.g. with imported favorites
+    pub fn replace(&self, bundle_ids: Vec<String>) -> Result<Vec<String>, String> {
+        self.update(|current| {
+            *current = bundle_ids;
+            Ok(())
+        })
+    }
+
+    fn update<F>(&self, change: F) -> Result<Vec<String>, String>
+    where
+        F: FnOnce(&mut Vec<String>) -> Result<(), String>,
+    {
+        let mut current = self.bundle_ids.lock().unwrap_or_else(|e| e.into_inner());
+        let mut updated = current.clone();
+        change(&mut updated)?;
+
+        save_favorites(&updated)?;
+        *current = updated.clone();
+        Ok(updated)
+    }
+}
+
+fn favorites_file() -> Result<PathBuf, String> {
+    Ok(settings::config_dir()?.join("favorites.json"))
+}
+
+fn load_favorites() -> Result<Vec<String>, String> {
+    let favorites_file = favorites_file()?;
+
+    if !favorites_file.exists() {
+        return Ok(Vec::new());
+    }
+
+    let contents = fs::read_to_string(&favorites_file)
+        .map_err(|e| format!("Failed to read favorites file: {}", e))?;
+
+    serde_json::from_str(&contents).map_err(|e| format!("Failed to parse favorites: {}", e))
+}
+
+fn save_favorites(bundle_ids: &[String]) -> Result<(), String> {
+    let favorites_file = favorites_file()?;
+
+    if let Some(dir) = favorites_file.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create config directory: {}", e))?;
+    }
+
+    let json = serde_json::to_string_pretty(bundle_ids)
+        .map_err(|e| format!("Failed to serialize favorites: {}", e))?;
+
+    // Write then rename so a crash never leaves a truncated favorites file
+    let temp_file = favorites_file.with_extension("json.tmp");
+    fs::write(&temp_file, json).map_err(|e| format!("Failed to write favorites file: {}", e))?;
+    fs::rename(&temp_file, &favorites_file)
+        .map_err(|e| format!("Failed to write favorites file: {}", e))
+}
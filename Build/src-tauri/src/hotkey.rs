@@ -0,0 +1,77 @@
+use crate::cmd;
+use crate::settings::{Settings, DEFAULT_HOTKEY};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+/// Tried in order when the configured shortcut can't be registered at startup
+const FALLBACK_HOTKEYS: &[&str] = &[DEFAULT_HOTKEY, "Ctrl+Shift+Space", "Alt+Shift+Space"];
+
+/// The shortcut currently bound to toggling the launcher
+#[derive(Default)]
+pub struct Hotkey {
+    active: Mutex<Option<Shortcut>>,
+}
+
+/// Binds the configured shortcut, falling back to the first free one from
+/// `FALLBACK_HOTKEYS` when it is invalid or taken by another application
+pub fn register_from_settings(app: &AppHandle, settings: &Settings) {
+    let candidates = std::iter::once(settings.hotkey.as_str())
+        .chain(FALLBACK_HOTKEYS.iter().copied())
+        .filter(|hotkey| !hotkey.is_empty());
+
+    for hotkey in candidates {
+        match bind(app, hotkey) {
+            Ok(()) => {
+                if hotkey != settings.hotkey {
+                    eprintln!(
+                        "Global shortcut '{}' is unavailable, using '{}' instead",
+                        settings.hotkey, hotkey
+                    );
+                }
+                return;
+            }
+            Err(e) => eprintln!("{}", e),
+        }
+    }
+
+    eprintln!("No global shortcut could be registered");
+}
+
+/// Registers `hotkey` as the toggle shortcut, releasing the previous one only
+/// once the new one is in place
+pub fn bind(app: &AppHandle, hotkey: &str) -> Result<(), String> {
+    let shortcut: Shortcut = hotkey
+        .parse()
+        .map_err(|e| format!("Invalid global shortcut '{}': {}", hotkey, e))?;
+
+    let state = app.state::<Hotkey>();
+    let mut active = state.active.lock().unwrap_or_else(|e| e.into_inner());
+    if *active == Some(shortcut) {
+        return Ok(());
+    }
+
+    let global_shortcut = app.global_shortcut();
+    if global_shortcut.is_registered(shortcut) {
+        return Err(format!("Global shortcut '{}' is already in use", hotkey));
+    }
+
+    // Registration fails when another application already owns the shortcut
+    global_shortcut
+        .on_shortcut(shortcut, |app, _, event| {
+            if event.state == ShortcutState::Pressed {
+                if let Err(e) = cmd::toggle_main_window(app) {
+                    eprintln!("Failed to toggle main window: {}", e);
+                }
+            }
+        })
+        .map_err(|e| format!("Global shortcut '{}' is unavailable: {}", hotkey, e))?;
+
+    if let Some(previous) = active.replace(shortcut) {
+        if let Err(e) = global_shortcut.unregister(previous) {
+            eprintln!("Failed to unregister global shortcut '{}': {}", previous, e);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,192 @@
+use crate::discovery::AppInfo;
+use crate::settings;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+/// Bumped whenever the snapshot layout changes; older snapshots are ignored
+const SNAPSHOT_VERSION: u32 = 2;
+
+#[derive(Serialize, Deserialize)]
+struct Snapshot {
+    version: u32,
+    saved_at: String,
+    apps: Vec<AppInfo>,
+}
+
+/// Changes between two scans, keyed by bundle id
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct IndexDiff {
+    pub added: Vec<AppInfo>,
+    pub removed: Vec<String>,
+    pub updated: Vec<AppInfo>,
+}
+
+impl IndexDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.updated.is_empty()
+    }
+}
+
+/// The last known app list, seeded from disk so the UI can render before
+/// discovery has finished
+#[derive(Default)]
+pub struct AppIndex {
+    apps: Mutex<Vec<AppInfo>>,
+    rescanning: AtomicBool,
+}
+
+impl AppIndex {
+    /// Loads the snapshot written by the previous run, if any
+    pub fn load() -> Self {
+        let apps = load_snapshot().unwrap_or_else(|e| {
+            eprintln!("Ignoring app index snapshot: {}", e);
+            Vec::new()
+        });
+
+        AppIndex {
+            apps: Mutex::new(apps),
+            rescanning: AtomicBool::new(false),
+        }
+    }
+
+    pub fn apps(&self) -> Vec<AppInfo> {
+        self.apps.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.apps
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .is_empty()
+    }
+
+    pub fn get(&self, bundle_id: &str) -> Option<AppInfo> {
+        self.apps
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .find(|app| app.bundle_id == bundle_id)
+            .cloned()
+    }
+
+    /// The indexed app with its canonical copy or an alternate at `path`
+    pub fn find_copy(&self, path: &str) -> Option<AppInfo> {
+        self.apps
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .find(|app| app.has_copy_at(path))
+            .cloned()
+    }
+
+    /// Whether `icon_path` is the icon of an indexed app
+    pub fn is_known_icon(&self, icon_path: &str) -> bool {
+        self.apps
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .any(|app| app.icon_path.as_deref() == Some(icon_path))
+    }
+
+    /// Replaces the index with a fresh scan, persists it and returns what changed
+    pub fn replace(&self, apps: Vec<AppInfo>) -> IndexDiff {
+        let mut current = self.apps.lock().unwrap_or_else(|e| e.into_inner());
+        let diff = diff_apps(&current, &apps);
+
+        if !diff.is_empty() || current.is_empty() {
+            if let Err(e) = save_snapshot(&apps) {
+                eprintln!("Failed to save app index snapshot: {}", e);
+            }
+        }
+
+        *current = apps;
+        diff
+    }
+
+    /// Marks a background rescan as running; returns false if one already is
+    pub fn begin_rescan(&self) -> bool {
+        !self.rescanning.swap(true, Ordering::AcqRel)
+    }
+
+    pub fn end_rescan(&self) {
+        self.rescanning.store(false, Ordering::Release);
+    }
+}
+
+pub fn diff_apps(old: &[AppInfo], new: &[AppInfo]) -> IndexDiff {
+    let old_by_id: HashMap<&str, &AppInfo> = old
+        .iter()
+        .map(|app| (app.bundle_id.as_str(), app))
+        .collect();
+    let new_by_id: HashMap<&str, &AppInfo> = new
+        .iter()
+        .map(|app| (app.bundle_id.as_str(), app))
+        .collect();
+
+    let mut diff = IndexDiff::default();
+
+    for app in new {
+        match old_by_id.get(app.bundle_id.as_str()) {
+            None => diff.added.push(app.clone()),
+            Some(previous) if *previous != app => diff.updated.push(app.clone()),
+            Some(_) => {}
+        }
+    }
+
+    for app in old {
+        if !new_by_id.contains_key(app.bundle_id.as_str()) {
+            diff.removed.push(app.bundle_id.clone());
+        }
+    }
+
+    diff
+}
+
+fn snapshot_file() -> Result<PathBuf, String> {
+    Ok(settings::config_dir()?.join("index.json"))
+}
+
+fn load_snapshot() -> Result<Vec<AppInfo>, String> {
+    let snapshot_file = snapshot_file()?;
+
+    if !snapshot_file.exists() {
+        return Ok(Vec::new());
+    }
+
+    let contents = fs::read_to_string(&snapshot_file)
+        .map_err(|e| format!("Failed to read index snapshot: {}", e))?;
+    let snapshot: Snapshot = serde_json::from_str(&contents)
+        .map_err(|e| format!("Failed to parse index snapshot: {}", e))?;
+
+    if snapshot.version != SNAPSHOT_VERSION {
+        return Err(format!("unsupported snapshot version {}", snapshot.version));
+    }
+
+    Ok(snapshot.apps)
+}
+
+fn save_snapshot(apps: &[AppInfo]) -> Result<(), String> {
+    let snapshot_file = snapshot_file()?;
+
+    let snapshot = Snapshot {
+        version: SNAPSHOT_VERSION,
+        saved_at: chrono::Utc::now().to_rfc3339(),
+        apps: apps.to_vec(),
+    };
+    let json = serde_json::to_string(&snapshot)
+        .map_err(|e| format!("Failed to serialize index snapshot: {}", e))?;
+
+    if let Some(dir) = snapshot_file.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create config directory: {}", e))?;
+    }
+
+    // Write through a temporary file so a crash never leaves a torn snapshot
+    let temp_file = snapshot_file.with_extension("json.tmp");
+    fs::write(&temp_file, json).map_err(|e| format!("Failed to write index snapshot: {}", e))?;
+    fs::rename(&temp_file, &snapshot_file)
+        .map_err(|e| format!("Failed to write index snapshot: {}", e))
+}
@@ -0,0 +1,555 @@
+use crate::discovery::AppInfo;
+use crate::running;
+use crate::settings;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long `open` may take to hand the app to LaunchServices
+pub const LAUNCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A launched app that exits within this window counts as crashed
+pub const CRASH_WINDOW: Duration = Duration::from_secs(3);
+
+/// Upper bound on the stderr kept for failure diagnostics
+const STDERR_LIMIT: usize = 16 * 1024;
+/// Size past which a launch log is emptied, so a chatty app that runs for
+/// days can't fill the disk
+const LOG_LIMIT: u64 = 1024 * 1024;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How often the process list is checked while watching for a crash
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// On macOS apps are started through `open`, a helper that exits once the
+/// app is launched; elsewhere the spawned process is the app itself
+const SPAWNS_HELPER: bool = cfg!(target_os = "macos");
+
+/// How an app should be started when handed files or URLs
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct OpenOptions {
+    /// Start another instance even if the app is already running
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+    pub new_instance: bool,
+    /// Launch without bringing the app to the foreground (macOS only)
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+    pub background: bool,
+    /// Extra command-line arguments passed to the app
+    pub args: Vec<String>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LaunchOutcome {
+    Launched,
+    /// The app was already running and brought to the front instead
+    Focused,
+    Failed,
+    TimedOut,
+    Crashed,
+}
+
+/// Emitted as `launch-finished` once a launch has settled
+#[derive(Serialize, Debug, Clone)]
+pub struct LaunchReport {
+    pub bundle_id: String,
+    pub outcome: LaunchOutcome,
+    pub duration_ms: u64,
+    pub pids: Vec<u32>,
+    pub exit_code: Option<i32>,
+    pub stderr: String,
+}
+
+impl LaunchReport {
+    pub fn focused(bundle_id: &str, pids: Vec<u32>) -> Self {
+        LaunchReport {
+            bundle_id: bundle_id.to_string(),
+            outcome: LaunchOutcome::Focused,
+            duration_ms: 0,
+            pids,
+            exit_code: None,
+            stderr: String::new(),
+        }
+    }
+
+    /// Turns failed outcomes into an error message for the command caller
+    pub fn into_result(self) -> Result<LaunchReport, String> {
+        let problem = match self.outcome {
+            LaunchOutcome::Launched | LaunchOutcome::Focused => return Ok(self),
+            LaunchOutcome::Failed => "failed to launch",
+            LaunchOutcome::TimedOut => "timed out while launching",
+            LaunchOutcome::Crashed => "exited right after launching",
+        };
+
+        if self.stderr.is_empty() {
+            Err(format!("'{}' {}", self.bundle_id, problem))
+        } else {
+            Err(format!("'{}' {}: {}", self.bundle_id, problem, self.stderr))
+        }
+    }
+}
+
+/// Checks that every target is either a URL or an existing file
+pub fn validate_targets(targets: &[String]) -> Result<(), String> {
+    for target in targets {
+        if !is_url(target) && !Path::new(target).exists() {
+            return Err(format!("File not found: {}", target));
+        }
+    }
+    Ok(())
+}
+
+fn is_url(target: &str) -> bool {
+    target.split_once("://").is_some_and(|(scheme, _)| {
+        !scheme.is_empty()
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+    })
+}
+
+/// Builds the `open` invocation for an app. `path` selects a bundle on disk;
+/// without it `app` is a bundle id, or a bundle path if it contains a slash.
+#[cfg(target_os = "macos")]
+pub fn launch_commands(
+    app: &str,
+    path: Option<&str>,
+    targets: &[String],
+    options: &OpenOptions,
+) -> Result<Vec<Command>, String> {
+    validate_targets(targets)?;
+
+    let mut command = Command::new("open");
+    if options.new_instance {
+        command.arg("-n");
+    }
+    if options.background {
+        command.arg("-g");
+    }
+
+    match path.or_else(|| app.contains('/').then_some(app)) {
+        Some(path) => command.arg("-a").arg(path),
+        None => command.arg("-b").arg(app),
+    };
+    command.args(targets);
+
+    if !options.args.is_empty() {
+        command.arg("--args").args(&options.args);
+    }
+
+    Ok(vec![command])
+}
+
+/// Builds the processes for an app. `path` picks what to run: a `.desktop`
+/// file gets one process per `Exec` expansion, and an executable is run
+/// with the targets as arguments; any other `path` is an error. Without a
+/// `path`, `app` is an entry id or `.desktop` path. `background` has no
+/// equivalent and every launch starts a new process.
+#[cfg(not(target_os = "macos"))]
+pub fn launch_commands(
+    app: &str,
+    path: Option<&str>,
+    targets: &[String],
+    options: &OpenOptions,
+) -> Result<Vec<Command>, String> {
+    validate_targets(targets)?;
+
+    match path {
+        // `.desktop` files are often marked executable too, so they go first
+        Some(path) if is_desktop_file(path) => entry_commands(Path::new(path), targets, options),
+        Some(path) if is_executable(Path::new(path)) => {
+            let mut command = Command::new(path);
+            command.args(targets).args(&options.args);
+            Ok(vec![command])
+        }
+        Some(path) => Err(format!(
+            "'{}' is neither a desktop entry nor an executable",
+            path
+        )),
+        None if is_desktop_file(app) => entry_commands(Path::new(app), targets, options),
+        None => {
+            let entry_path = crate::desktop_entry::find_desktop_entry(app)
+                .ok_or_else(|| format!("No desktop entry found for '{}'", app))?;
+            entry_commands(&entry_path, targets, options)
+        }
+    }
+}
+
+/// One process per `Exec` expansion of the desktop entry at `entry_path`
+#[cfg(not(target_os = "macos"))]
+fn entry_commands(
+    entry_path: &Path,
+    targets: &[String],
+    options: &OpenOptions,
+) -> Result<Vec<Command>, String> {
+    use crate::desktop_entry::{self, DesktopEntry};
+
+    let entry = DesktopEntry::load(entry_path)?;
+
+    desktop_entry::expand_exec(&entry, targets)?
+        .into_iter()
+        .map(|mut argv| {
+            argv.extend(options.args.iter().cloned());
+            let (program, args) = argv
+                .split_first()
+                .ok_or_else(|| "Desktop entry has an empty Exec line".to_string())?;
+
+            let mut command = Command::new(program);
+            command.args(args);
+            Ok(command)
+        })
+        .collect()
+}
+
+#[cfg(not(target_os = "macos"))]
+fn is_desktop_file(path: &str) -> bool {
+    path.ends_with(".desktop") && Path::new(path).is_file()
+}
+
+/// A regular file with an execute bit set
+#[cfg(all(unix, not(target_os = "macos")))]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path)
+        .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file() && path.extension().is_some_and(|extension| extension == "exe")
+}
+
+/// Spawns the launch commands and returns as soon as they are running, so
+/// callers never wait out the crash window. How the launch settles is handed
+/// to `on_settled` from a background thread. On macOS the process is `open`,
+/// which exits once LaunchServices has the app, so it is bounded by
+/// `LAUNCH_TIMEOUT` and `watch` is then polled for a crash. Elsewhere the
+/// process is the app itself, which crashed if it fails within
+/// `CRASH_WINDOW`. A launch that can't be spawned settles right away.
+pub fn launch<F>(
+    app: &str,
+    commands: Vec<Command>,
+    watch: Option<AppInfo>,
+    on_settled: F,
+) -> LaunchReport
+where
+    F: FnOnce(LaunchReport) + Send + 'static,
+{
+    let started = Instant::now();
+    let log = LaunchLog::create(app);
+    let mut report = LaunchReport {
+        bundle_id: app.to_string(),
+        outcome: LaunchOutcome::Launched,
+        duration_ms: 0,
+        pids: Vec::new(),
+        exit_code: None,
+        stderr: String::new(),
+    };
+    let mut children = Vec::new();
+
+    for mut command in commands {
+        command
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(log.stdio());
+
+        match command.spawn() {
+            Ok(child) => children.push(child),
+            Err(e) => {
+                report.outcome = LaunchOutcome::Failed;
+                report.stderr = format!("Failed to start {:?}: {}", command.get_program(), e);
+                break;
+            }
+        }
+    }
+
+    if !SPAWNS_HELPER {
+        report.pids = children.iter().map(Child::id).collect();
+    }
+    report.duration_ms = started.elapsed().as_millis() as u64;
+
+    if report.outcome != LaunchOutcome::Launched {
+        reap(children, log);
+        on_settled(report.clone());
+        return report;
+    }
+
+    let pending = report.clone();
+    thread::spawn(move || {
+        let settled = settle(pending, &mut children, watch.as_ref(), &log, started);
+        on_settled(settled);
+        reap(children, log);
+    });
+    report
+}
+
+/// Waits for spawned launch commands to settle and fills in the outcome
+fn settle(
+    mut report: LaunchReport,
+    children: &mut [Child],
+    watch: Option<&AppInfo>,
+    log: &LaunchLog,
+    started: Instant,
+) -> LaunchReport {
+    let limit = if SPAWNS_HELPER {
+        LAUNCH_TIMEOUT
+    } else {
+        CRASH_WINDOW
+    };
+    let deadline = started + limit;
+
+    for child in children.iter_mut() {
+        match wait_until(child, deadline) {
+            Ok(Some(status)) => {
+                report.exit_code = status.code();
+                if !status.success() {
+                    report.outcome = if SPAWNS_HELPER {
+                        LaunchOutcome::Failed
+                    } else {
+                        LaunchOutcome::Crashed
+                    };
+                    break;
+                }
+            }
+            Ok(None) if SPAWNS_HELPER => {
+                let _ = child.kill();
+                let _ = child.wait();
+                report.outcome = LaunchOutcome::TimedOut;
+                break;
+            }
+            // The app outlived the crash window
+            Ok(None) => {}
+            Err(e) => {
+                report.outcome = LaunchOutcome::Failed;
+                report.stderr = format!("Failed to wait for launch: {}", e);
+                break;
+            }
+        }
+    }
+
+    if SPAWNS_HELPER && report.outcome == LaunchOutcome::Launched {
+        if let Some(app) = watch {
+            let (outcome, pids) = watch_for_crash(app);
+            report.outcome = outcome;
+            report.pids = pids;
+        }
+    }
+
+    if report.outcome != LaunchOutcome::Launched && report.stderr.is_empty() {
+        report.stderr = log.read();
+    }
+    report.duration_ms = started.elapsed().as_millis() as u64;
+    report
+}
+
+/// Polls a child until it exits or `deadline` passes
+fn wait_until(child: &mut Child, deadline: Instant) -> io::Result<Option<ExitStatus>> {
+    loop {
+        match child.try_wait()? {
+            Some(status) => return Ok(Some(status)),
+            None if Instant::now() < deadline => thread::sleep(POLL_INTERVAL),
+            None => return Ok(None),
+        }
+    }
+}
+
+/// Collects the exit status of launched processes whenever they exit,
+/// keeping their log under `LOG_LIMIT` while they run
+fn reap(mut children: Vec<Child>, log: LaunchLog) {
+    if children.is_empty() {
+        return;
+    }
+    thread::spawn(move || loop {
+        log.cap();
+        children.retain_mut(|child| matches!(child.try_wait(), Ok(None)));
+        if children.is_empty() {
+            return;
+        }
+        thread::sleep(WATCH_INTERVAL);
+    });
+}
+
+/// Polls the process list for `CRASH_WINDOW`; an app seen running and then
+/// gone counts as crashed. Apps that never show up can't be judged, so
+/// they are reported as launched.
+fn watch_for_crash(app: &AppInfo) -> (LaunchOutcome, Vec<u32>) {
+    let deadline = Instant::now() + CRASH_WINDOW;
+    let mut seen = false;
+
+    loop {
+        let pids = running::running_apps(std::slice::from_ref(app))
+            .pop()
+            .map(|running| running.pids)
+            .unwrap_or_default();
+
+        if seen && pids.is_empty() {
+            return (LaunchOutcome::Crashed, pids);
+        }
+        seen |= !pids.is_empty();
+
+        if Instant::now() >= deadline {
+            return (LaunchOutcome::Launched, pids);
+        }
+        thread::sleep(WATCH_INTERVAL);
+    }
+}
+
+/// Where a launched app's stderr goes. A file rather than a pipe, so the
+/// app can keep writing after the launcher exits; each launch of an app
+/// overwrites its previous log. A log that grows past `LOG_LIMIT` is
+/// emptied, which is safe since apps only ever append to it. Without one,
+/// stderr is discarded.
+struct LaunchLog {
+    path: Option<PathBuf>,
+}
+
+impl LaunchLog {
+    /// Starts a fresh log for `app`. Logs of apps still writing after the
+    /// launcher that started them exited are capped here too.
+    fn create(app: &str) -> Self {
+        let path = launch_log_path(app).and_then(|path| {
+            if let Some(entries) = path.parent().and_then(|dir| fs::read_dir(dir).ok()) {
+                for entry in entries.flatten() {
+                    cap_log(&entry.path());
+                }
+            }
+            fs::File::create(&path).map_err(|e| format!("Failed to create launch log: {}", e))?;
+            Ok(path)
+        });
+
+        match path {
+            Ok(path) => LaunchLog { path: Some(path) },
+            Err(e) => {
+                eprintln!("{}", e);
+                LaunchLog { path: None }
+            }
+        }
+    }
+
+    fn stdio(&self) -> Stdio {
+        self.path
+            .as_ref()
+            .and_then(|path| fs::OpenOptions::new().append(true).open(path).ok())
+            .map(Stdio::from)
+            .unwrap_or_else(Stdio::null)
+    }
+
+    fn cap(&self) {
+        if let Some(path) = &self.path {
+            cap_log(path);
+        }
+    }
+
+    /// The first `STDERR_LIMIT` bytes written so far
+    fn read(&self) -> String {
+        let mut buffer = Vec::new();
+        if let Some(file) = self
+            .path
+            .as_ref()
+            .and_then(|path| fs::File::open(path).ok())
+        {
+            let _ = file.take(STDERR_LIMIT as u64).read_to_end(&mut buffer);
+        }
+        String::from_utf8_lossy(&buffer).trim().to_string()
+    }
+}
+
+/// Empties the log at `path` once it is larger than `LOG_LIMIT`
+fn cap_log(path: &Path) {
+    let oversized = fs::metadata(path).is_ok_and(|metadata| metadata.len() > LOG_LIMIT);
+    if oversized {
+        if let Err(e) = fs::OpenOptions::new()
+            .write(true)
+            .open(path)
+            .and_then(|file| file.set_len(0))
+        {
+            eprintln!("Failed to truncate {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// `logs/<app>.log` in the config directory, with anything that isn't safe
+/// in a file name replaced
+fn launch_log_path(app: &str) -> Result<PathBuf, String> {
+    let dir = settings::config_dir()?.join("logs");
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create log directory: {}", e))?;
+
+    let name: String = app
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || "._-".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    Ok(dir.join(format!("{}.log", name)))
+}
+
+/// Brings `app` to the front if it is running. Returns None when it isn't,
+/// or when focusing failed and the caller should launch it instead.
+pub fn focus_if_running(app: &AppInfo) -> Option<LaunchReport> {
+    let running = running::running_apps(std::slice::from_ref(app)).pop()?;
+
+    match focus(&app.bundle_id, &running.pids) {
+        Ok(()) => Some(LaunchReport::focused(&app.bundle_id, running.pids)),
+        Err(e) => {
+            eprintln!("{}, launching instead", e);
+            None
+        }
+    }
+}
+
+/// Brings an already running app to the front
+#[cfg(target_os = "macos")]
+pub fn focus(bundle_id: &str, _pids: &[u32]) -> Result<(), String> {
+    let script = format!(
+        "tell application id \"{}\" to activate",
+        escape_applescript(bundle_id)
+    );
+
+    let output = Command::new("osascript")
+        .arg("-e")
+        .arg(script)
+        .output()
+        .map_err(|e| format!("Failed to run osascript: {}", e))?;
+
+    if !output.status.success() {
+        let error_msg = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("Failed to focus '{}': {}", bundle_id, error_msg));
+    }
+
+    Ok(())
+}
+
+/// Brings an already running app to the front by activating the first
+/// window owned by one of its processes
+#[cfg(not(target_os = "macos"))]
+pub fn focus(bundle_id: &str, pids: &[u32]) -> Result<(), String> {
+    for pid in pids {
+        let activated = Command::new("xdotool")
+            .args(["search", "--onlyvisible", "--pid"])
+            .arg(pid.to_string())
+            .arg("windowactivate")
+            .output()
+            .is_ok_and(|output| output.status.success());
+
+        if activated {
+            return Ok(());
+        }
+    }
+
+    Err(format!("No window found for '{}'", bundle_id))
+}
+
+#[cfg(target_os = "macos")]
+pub fn escape_applescript(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -1,24 +1,111 @@
 // Prevents additional console window on macOS in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod backup;
+mod cli;
 mod cmd;
+mod config_watch;
+#[cfg(unix)]
+mod control;
+#[cfg(target_os = "linux")]
+mod dbus;
+#[cfg(not(target_os = "macos"))]
+mod desktop_entry;
+mod discovery;
+mod error;
+mod favorites;
+mod hotkey;
+mod index;
+mod launcher;
+mod quit;
+mod running;
+mod search;
+mod settings;
+mod single_instance;
+mod stats;
+mod trash;
+mod tray;
+mod unused;
+mod usage;
 use cmd::{
-    get_frequent_apps, hide_main_window, list_apps, open_app, show_main_window, track_app_usage,
+    add_favorite, export_state, export_usage_stats, force_quit_app, forget_app_usage,
+    get_app_icon_base64, get_favorites, get_frequent_apps, get_settings, get_settings_error,
+    get_trash_history, get_unused_apps, get_usage_stats, hide_main_window, import_state, list_apps,
+    list_running_apps, open_app, open_with, purge_usage_history, quit_app, read_icon_file,
+    remove_favorite, reorder_favorites, restore_trashed_app, search_apps, set_hotkey,
+    set_usage_excluded, set_usage_tracking_paused, show_main_window, track_app_usage, trash_app,
+    trash_unused_apps, update_settings,
 };
+use favorites::FavoritesStore;
+use hotkey::Hotkey;
+use index::AppIndex;
+use settings::SettingsStore;
 use tauri::Manager;
-use tauri::WindowEvent;
+use tauri::{RunEvent, WindowEvent};
+use trash::TrashHistory;
+use unused::BundleSizes;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    run_launcher(Vec::new());
+}
+
+/// Entry point of the `relaunchpad` binary; returns the exit code
+pub fn main() -> i32 {
+    cli::main(std::env::args().skip(1).collect())
+}
+
+/// Starts the launcher window. `args` are launcher flags such as `--toggle`,
+/// already checked by the command-line parser.
+pub(crate) fn run_launcher(args: Vec<String>) -> i32 {
+    // A second launch hands its arguments to the running instance and exits
+    match single_instance::forward(&args) {
+        Ok(true) => return cli::EXIT_OK,
+        Ok(false) => {}
+        Err(e) => {
+            eprintln!("relaunchpad: {}", e);
+            return cli::EXIT_FAILURE;
+        }
+    }
+
     tauri::Builder::default()
-        .setup(|app| {
+        .plugin(tauri_plugin_global_shortcut::Builder::new().build())
+        .manage(AppIndex::load())
+        .manage(Hotkey::default())
+        .manage(SettingsStore::load())
+        .manage(FavoritesStore::load())
+        .manage(BundleSizes::load())
+        .manage(TrashHistory::load())
+        .setup(move |app| {
+            let settings = app.state::<SettingsStore>().get();
             let window = app.get_webview_window("main").unwrap();
             // Configure window behavior
-            window.set_always_on_top(true).unwrap();
+            window.set_always_on_top(settings.always_on_top).unwrap();
 
             // Remove window decorations for cleaner look
             window.set_decorations(false).unwrap();
 
+            hotkey::register_from_settings(app.handle(), &settings);
+            if let Err(e) = tray::create(app.handle()) {
+                eprintln!("Failed to create tray icon: {}", e);
+            }
+            config_watch::watch_settings(app.handle().clone());
+
+            if let Err(e) = single_instance::listen(app.handle().clone()) {
+                eprintln!("Later launches will start a separate instance: {}", e);
+            }
+            #[cfg(unix)]
+            if let Err(e) = control::listen(app.handle().clone()) {
+                eprintln!("Control socket is unavailable: {}", e);
+            }
+            #[cfg(target_os = "linux")]
+            if let Err(e) = dbus::serve(app.handle().clone()) {
+                eprintln!("D-Bus interface is unavailable: {}", e);
+            }
+            if let Err(e) = single_instance::handle_args(app.handle(), &args) {
+                eprintln!("{}", e);
+            }
+
             #[cfg(debug_assertions)]
             {
                 window.open_devtools();
@@ -33,7 +120,9 @@ pub fn run() {
                     api.prevent_close();
                     window.hide().unwrap();
                 }
-                WindowEvent::Focused(false) => {
+                WindowEvent::Focused(false)
+                    if window.state::<SettingsStore>().get().hide_on_blur =>
+                {
                     // Hide window when it loses focus (like Spotlight)
                     window.hide().unwrap();
                 }
@@ -42,12 +131,49 @@ pub fn run() {
         })
         .invoke_handler(tauri::generate_handler![
             list_apps,
+            list_running_apps,
+            search_apps,
             get_frequent_apps,
+            get_favorites,
+            add_favorite,
+            remove_favorite,
+            reorder_favorites,
             open_app,
+            open_with,
+            quit_app,
+            force_quit_app,
             track_app_usage,
+            set_usage_tracking_paused,
+            set_usage_excluded,
+            forget_app_usage,
+            purge_usage_history,
+            get_usage_stats,
+            export_usage_stats,
+            export_state,
+            import_state,
+            get_unused_apps,
+            trash_unused_apps,
+            trash_app,
+            restore_trashed_app,
+            get_trash_history,
             show_main_window,
-            hide_main_window
+            hide_main_window,
+            set_hotkey,
+            get_settings,
+            get_settings_error,
+            update_settings,
+            get_app_icon_base64,
+            read_icon_file
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running ReLaunchpad");
+        .build(tauri::generate_context!())
+        .expect("error while running ReLaunchpad")
+        .run(|_, event| {
+            if let RunEvent::Exit = event {
+                single_instance::cleanup();
+                #[cfg(unix)]
+                control::cleanup();
+            }
+        });
+
+    cli::EXIT_OK
 }
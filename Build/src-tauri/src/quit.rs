@@ -0,0 +1,194 @@
+use crate::discovery::AppInfo;
+use crate::error::CommandError;
+use crate::running::{self, ProcessInfo};
+use serde::Serialize;
+use std::path::Path;
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// ReLaunchpad's own bundle identifier; the launcher never quits itself
+pub const LAUNCHER_BUNDLE_ID: &str = "com.nellowtcs.relaunchpad";
+
+/// How long a graceful quit waits for processes to exit
+pub const DEFAULT_QUIT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Apps the session depends on, which are never quit from the launcher
+const PROTECTED_BUNDLE_IDS: &[&str] = &[
+    LAUNCHER_BUNDLE_ID,
+    "com.apple.finder",
+    "com.apple.dock",
+    "com.apple.loginwindow",
+    "com.apple.systemuiserver",
+    "com.apple.controlcenter",
+    "com.apple.WindowManager",
+];
+
+/// Executables that keep the session alive, matched by file name
+const PROTECTED_PROCESSES: &[&str] = &[
+    "launchd",
+    "kernel_task",
+    "WindowServer",
+    "loginwindow",
+    "init",
+    "systemd",
+    "dbus-daemon",
+    "Xorg",
+    "Xwayland",
+    "gnome-shell",
+    "plasmashell",
+    "kwin_x11",
+    "kwin_wayland",
+];
+
+/// Which of an app's processes exited after being asked to quit
+#[derive(Serialize, Debug, Clone)]
+pub struct QuitReport {
+    pub bundle_id: String,
+    pub terminated: Vec<u32>,
+    pub still_running: Vec<u32>,
+    /// True when processes had to be killed after the timeout
+    pub forced: bool,
+}
+
+/// Asks an app to quit (AppleScript on macOS, SIGTERM elsewhere) and waits
+/// up to `timeout` for its processes to exit
+pub fn quit_app(app: &AppInfo, timeout: Duration) -> Result<QuitReport, CommandError> {
+    let pids = target_pids(app)?;
+
+    request_quit(app, &pids)?;
+    let still_running = wait_for_exit(&pids, timeout);
+
+    Ok(report(app, &pids, still_running, false))
+}
+
+/// Sends SIGTERM, then SIGKILLs whatever is still running after `timeout`
+pub fn force_quit_app(app: &AppInfo, timeout: Duration) -> Result<QuitReport, CommandError> {
+    let pids = target_pids(app)?;
+
+    send_signal("TERM", &pids)?;
+    let mut still_running = wait_for_exit(&pids, timeout);
+
+    let forced = !still_running.is_empty();
+    if forced {
+        send_signal("KILL", &still_running)?;
+        still_running = wait_for_exit(&still_running, Duration::from_secs(1));
+    }
+
+    Ok(report(app, &pids, still_running, forced))
+}
+
+/// Resolves an app to its running processes, refusing protected targets
+fn target_pids(app: &AppInfo) -> Result<Vec<u32>, CommandError> {
+    if PROTECTED_BUNDLE_IDS.contains(&app.bundle_id.as_str()) {
+        return Err(CommandError::NotAllowed(format!(
+            "Refusing to quit protected app '{}'",
+            app.bundle_id
+        )));
+    }
+
+    let processes = running::list_processes();
+    let pids = running::match_running(std::slice::from_ref(app), &processes)
+        .pop()
+        .map(|running| running.pids)
+        .ok_or_else(|| format!("'{}' is not running", app.bundle_id))?;
+
+    let own_pid = std::process::id();
+    for process in processes.iter().filter(|p| pids.contains(&p.pid)) {
+        if process.pid <= 1 || process.pid == own_pid || is_protected_process(process) {
+            return Err(CommandError::NotAllowed(format!(
+                "Refusing to quit protected process {} of '{}'",
+                process.pid, app.bundle_id
+            )));
+        }
+    }
+
+    Ok(pids)
+}
+
+fn is_protected_process(process: &ProcessInfo) -> bool {
+    let program = process
+        .exe
+        .as_deref()
+        .or_else(|| process.cmdline.first().map(Path::new));
+
+    program
+        .and_then(|program| program.file_name())
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| PROTECTED_PROCESSES.contains(&name))
+}
+
+#[cfg(target_os = "macos")]
+fn request_quit(app: &AppInfo, _pids: &[u32]) -> Result<(), String> {
+    let script = format!(
+        "tell application id \"{}\" to quit",
+        crate::launcher::escape_applescript(&app.bundle_id)
+    );
+
+    let output = Command::new("osascript")
+        .arg("-e")
+        .arg(script)
+        .output()
+        .map_err(|e| format!("Failed to run osascript: {}", e))?;
+
+    if !output.status.success() {
+        let error_msg = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("Failed to quit '{}': {}", app.bundle_id, error_msg));
+    }
+
+    Ok(())
+}
+
+#[cfg(not(target_os = "macos"))]
+fn request_quit(_app: &AppInfo, pids: &[u32]) -> Result<(), String> {
+    send_signal("TERM", pids)
+}
+
+fn send_signal(signal: &str, pids: &[u32]) -> Result<(), String> {
+    if pids.is_empty() {
+        return Ok(());
+    }
+
+    // A non-zero status only means some processes were already gone; the
+    // caller finds out which by checking what is still running
+    Command::new("kill")
+        .arg("-s")
+        .arg(signal)
+        .args(pids.iter().map(u32::to_string))
+        .output()
+        .map_err(|e| format!("Failed to run kill: {}", e))?;
+
+    Ok(())
+}
+
+/// Polls until every pid has exited or `timeout` passes, returning the
+/// pids that are still alive
+fn wait_for_exit(pids: &[u32], timeout: Duration) -> Vec<u32> {
+    let deadline = Instant::now() + timeout;
+
+    loop {
+        let alive: Vec<u32> = running::list_processes()
+            .into_iter()
+            .map(|process| process.pid)
+            .filter(|pid| pids.contains(pid))
+            .collect();
+
+        if alive.is_empty() || Instant::now() >= deadline {
+            return alive;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+}
+
+fn report(app: &AppInfo, pids: &[u32], still_running: Vec<u32>, forced: bool) -> QuitReport {
+    QuitReport {
+        bundle_id: app.bundle_id.clone(),
+        terminated: pids
+            .iter()
+            .copied()
+            .filter(|pid| !still_running.contains(pid))
+            .collect(),
+        still_running,
+        forced,
+    }
+}
@@ -0,0 +1,136 @@
+use crate::discovery::AppInfo;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// An indexed app with at least one live process
+#[derive(Serialize, Debug, Clone)]
+pub struct RunningApp {
+    pub bundle_id: String,
+    pub name: String,
+    pub pids: Vec<u32>,
+}
+
+/// A process and the paths that can tie it back to an app bundle
+#[derive(Debug, Clone)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub exe: Option<PathBuf>,
+    pub cmdline: Vec<String>,
+}
+
+impl ProcessInfo {
+    /// Whether the process runs an executable from inside `bundle_path`
+    fn runs_from(&self, bundle_path: &Path) -> bool {
+        let from_cmdline = self
+            .cmdline
+            .first()
+            .map(Path::new)
+            .filter(|program| program.is_absolute());
+
+        self.exe
+            .as_deref()
+            .into_iter()
+            .chain(from_cmdline)
+            .any(|program| program.starts_with(bundle_path))
+    }
+}
+
+/// Lists processes from `/proc`. Processes owned by other users usually hide
+/// their `exe` link, in which case the command line is used instead.
+#[cfg(target_os = "linux")]
+pub fn list_processes() -> Vec<ProcessInfo> {
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let pid: u32 = entry.file_name().to_str()?.parse().ok()?;
+            let dir = entry.path();
+
+            let exe = std::fs::read_link(dir.join("exe")).ok();
+            let cmdline = std::fs::read(dir.join("cmdline"))
+                .map(|raw| {
+                    raw.split(|b| *b == 0)
+                        .filter(|arg| !arg.is_empty())
+                        .map(|arg| String::from_utf8_lossy(arg).into_owned())
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            Some(ProcessInfo { pid, exe, cmdline })
+        })
+        .collect()
+}
+
+/// Lists processes via `ps`, whose `comm` column holds the executable path
+#[cfg(target_os = "macos")]
+pub fn list_processes() -> Vec<ProcessInfo> {
+    let Ok(output) = std::process::Command::new("ps")
+        .args(["-axo", "pid=,comm="])
+        .output()
+    else {
+        return Vec::new();
+    };
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let (pid, exe) = line.trim().split_once(char::is_whitespace)?;
+            Some(ProcessInfo {
+                pid: pid.parse().ok()?,
+                exe: Some(PathBuf::from(exe.trim())),
+                cmdline: Vec::new(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn list_processes() -> Vec<ProcessInfo> {
+    Vec::new()
+}
+
+/// Matches processes to apps by checking which bundle their executable lives
+/// in. Every installed copy of an app counts.
+pub fn match_running(apps: &[AppInfo], processes: &[ProcessInfo]) -> Vec<RunningApp> {
+    apps.iter()
+        .filter_map(|app| {
+            let copies: Vec<&Path> = std::iter::once(app.path.as_str())
+                .chain(app.alternates.iter().map(|copy| copy.path.as_str()))
+                .map(Path::new)
+                .collect();
+
+            let pids: Vec<u32> = processes
+                .iter()
+                .filter(|process| copies.iter().any(|copy| process.runs_from(copy)))
+                .map(|process| process.pid)
+                .collect();
+
+            (!pids.is_empty()).then(|| RunningApp {
+                bundle_id: app.bundle_id.clone(),
+                name: app.name.clone(),
+                pids,
+            })
+        })
+        .collect()
+}
+
+/// Finds the running apps among `apps` using a fresh process listing
+pub fn running_apps(apps: &[AppInfo]) -> Vec<RunningApp> {
+    match_running(apps, &list_processes())
+}
+
+/// Sets the `running` flag on each app
+pub fn mark_running(apps: &mut [AppInfo]) {
+    let running: HashSet<String> = running_apps(apps)
+        .into_iter()
+        .map(|app| app.bundle_id)
+        .collect();
+
+    for app in apps {
+        app.running = running.contains(&app.bundle_id);
+    }
+}
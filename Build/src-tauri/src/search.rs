@@ -0,0 +1,49 @@
+use crate::discovery::AppInfo;
+
+/// How well an app matches a query; lower sorts first
+fn rank(app: &AppInfo, query: &str) -> Option<u8> {
+    let name = app.name.to_lowercase();
+
+    if name == query {
+        Some(0)
+    } else if name.starts_with(query) {
+        Some(1)
+    } else if name.split_whitespace().any(|word| word.starts_with(query)) {
+        Some(2)
+    } else if name.contains(query) {
+        Some(3)
+    } else if app.bundle_id.to_lowercase().contains(query) {
+        Some(4)
+    } else {
+        None
+    }
+}
+
+/// Apps whose name or bundle id contains `query`, ignoring case. Exact and
+/// prefix name matches come first, ties are ordered by name. An empty query
+/// matches every app, with `favorites` first in their pinned order.
+pub fn search_apps(apps: &[AppInfo], query: &str, favorites: &[String]) -> Vec<AppInfo> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return favorites_first(apps, favorites);
+    }
+
+    let mut matches: Vec<(u8, &AppInfo)> = apps
+        .iter()
+        .filter_map(|app| rank(app, &query).map(|rank| (rank, app)))
+        .collect();
+    matches.sort_by_key(|(rank, app)| (*rank, app.name.to_lowercase()));
+
+    matches.into_iter().map(|(_, app)| app.clone()).collect()
+}
+
+fn favorites_first(apps: &[AppInfo], favorites: &[String]) -> Vec<AppInfo> {
+    let pinned = favorites
+        .iter()
+        .filter_map(|bundle_id| apps.iter().find(|app| &app.bundle_id == bundle_id));
+    let rest = apps
+        .iter()
+        .filter(|app| !favorites.contains(&app.bundle_id));
+
+    pinned.chain(rest).cloned().collect()
+}
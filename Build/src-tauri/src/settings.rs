@@ -0,0 +1,230 @@
+use crate::discovery::{self, DiscoveryConfig};
+use crate::usage::UsageTracking;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Global shortcut that summons the launcher unless the user picks another
+pub const DEFAULT_HOTKEY: &str = "Ctrl+Alt+Space";
+
+/// Upper bound for `max_frequent_apps`
+const MAX_FREQUENT_APPS_LIMIT: usize = 100;
+
+/// User configuration, persisted as JSON in the config directory.
+/// Missing fields fall back to their defaults so older files keep loading.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    pub discovery: DiscoveryConfig,
+    /// Accelerator string such as `Ctrl+Alt+Space`
+    pub hotkey: String,
+    /// How many apps `get_frequent_apps` returns
+    pub max_frequent_apps: usize,
+    /// Hide the launcher when it loses focus, like Spotlight
+    pub hide_on_blur: bool,
+    pub always_on_top: bool,
+    /// Where launch counts are kept; a leading `~` is the home directory
+    pub usage_file: String,
+    pub usage_tracking: UsageTracking,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            discovery: DiscoveryConfig::default(),
+            hotkey: DEFAULT_HOTKEY.to_string(),
+            max_frequent_apps: 20,
+            hide_on_blur: true,
+            always_on_top: true,
+            usage_file: "~/.relaunchpad_usage.json".to_string(),
+            usage_tracking: UsageTracking::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// Loads the settings file, falling back to defaults if it is missing or broken
+    pub fn load() -> Self {
+        load_settings().unwrap_or_else(|e| {
+            eprintln!("Using default settings: {}", e);
+            Settings::default()
+        })
+    }
+
+    /// Writes the settings file, creating the config directory if needed
+    pub fn save(&self) -> Result<(), String> {
+        save_settings(self)
+    }
+
+    /// Rejects values the rest of the app can't work with
+    pub fn validate(&self) -> Result<(), String> {
+        if self.hotkey.trim().is_empty() {
+            return Err("Global shortcut cannot be empty".to_string());
+        }
+        if !(1..=MAX_FREQUENT_APPS_LIMIT).contains(&self.max_frequent_apps) {
+            return Err(format!(
+                "Frequent apps must be between 1 and {}",
+                MAX_FREQUENT_APPS_LIMIT
+            ));
+        }
+        if !self.usage_file_path().is_absolute() {
+            return Err(format!(
+                "Usage file '{}' must be an absolute path",
+                self.usage_file
+            ));
+        }
+        if let Some(root) = self
+            .discovery
+            .roots
+            .iter()
+            .find(|r| r.path.trim().is_empty())
+        {
+            return Err(format!(
+                "Discovery root with depth {} has no path",
+                root.max_depth
+            ));
+        }
+        if self.discovery.exclude.iter().any(|e| e.trim().is_empty()) {
+            return Err("Discovery exclusions cannot be empty".to_string());
+        }
+        if self
+            .usage_tracking
+            .excluded
+            .iter()
+            .any(|id| id.trim().is_empty())
+        {
+            return Err("Apps excluded from usage tracking need a bundle id".to_string());
+        }
+        Ok(())
+    }
+
+    /// `usage_file` with `~` expanded
+    pub fn usage_file_path(&self) -> PathBuf {
+        discovery::expand_home(&self.usage_file)
+    }
+}
+
+/// The settings in effect, shared with commands. Updates are saved before
+/// they replace the current value, so memory and disk never disagree.
+pub struct SettingsStore {
+    current: Mutex<Settings>,
+    /// Why the settings file isn't in effect, while it fails to load
+    error: Mutex<Option<String>>,
+}
+
+impl SettingsStore {
+    /// Loads the settings file, falling back to defaults if it is missing or
+    /// broken. Why a broken file was skipped is kept for `error`.
+    pub fn load() -> Self {
+        let (settings, error) = match load_settings() {
+            Ok(settings) => (settings, None),
+            Err(e) => {
+                eprintln!("Using default settings: {}", e);
+                (Settings::default(), Some(e))
+            }
+        };
+
+        SettingsStore {
+            current: Mutex::new(settings),
+            error: Mutex::new(error),
+        }
+    }
+
+    pub fn get(&self) -> Settings {
+        self.current
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Saves `settings` and makes them current
+    pub fn replace(&self, settings: Settings) -> Result<(), String> {
+        let mut current = self.current.lock().unwrap_or_else(|e| e.into_inner());
+        settings.save()?;
+        *current = settings;
+        Ok(())
+    }
+
+    /// Makes `settings` current without writing them, for values that were
+    /// just read from the settings file
+    pub fn set(&self, settings: Settings) {
+        *self.current.lock().unwrap_or_else(|e| e.into_inner()) = settings;
+    }
+
+    pub fn error(&self) -> Option<String> {
+        self.error.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Records why the settings file failed to load, or that it loads again
+    pub fn set_error(&self, error: Option<String>) {
+        *self.error.lock().unwrap_or_else(|e| e.into_inner()) = error;
+    }
+}
+
+/// Per-user configuration directory for ReLaunchpad
+pub fn config_dir() -> Result<PathBuf, String> {
+    let home_dir = std::env::var("HOME").map_err(|_| "Could not find home directory")?;
+
+    if cfg!(target_os = "macos") {
+        return Ok(Path::new(&home_dir)
+            .join("Library/Application Support")
+            .join("com.nellowtcs.relaunchpad"));
+    }
+
+    let base = std::env::var("XDG_CONFIG_HOME")
+        .ok()
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| Path::new(&home_dir).join(".config"));
+    Ok(base.join("relaunchpad"))
+}
+
+pub fn settings_file() -> Result<PathBuf, String> {
+    Ok(config_dir()?.join("settings.json"))
+}
+
+/// Reads and validates the settings file; a missing file means defaults
+pub fn load_settings() -> Result<Settings, String> {
+    let settings_file = settings_file()?;
+
+    if !settings_file.exists() {
+        return Ok(Settings::default());
+    }
+
+    let contents = fs::read_to_string(&settings_file)
+        .map_err(|e| format!("Failed to read settings file: {}", e))?;
+
+    let settings: Settings =
+        serde_json::from_str(&contents).map_err(|e| format!("Failed to parse settings: {}", e))?;
+    settings.validate()?;
+    Ok(settings)
+}
+
+/// Writes the settings file. A file that doesn't load is copied to
+/// `settings.json.bak` first, so hand edits that failed to parse aren't lost.
+fn save_settings(settings: &Settings) -> Result<(), String> {
+    let settings_file = settings_file()?;
+
+    if let Some(dir) = settings_file.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create config directory: {}", e))?;
+    }
+    if settings_file.exists() && load_settings().is_err() {
+        let backup = settings_file.with_extension("json.bak");
+        fs::copy(&settings_file, &backup)
+            .map_err(|e| format!("Failed to back up settings file: {}", e))?;
+        eprintln!(
+            "Backed up the unreadable settings file to {}",
+            backup.display()
+        );
+    }
+
+    let json = serde_json::to_string_pretty(settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+
+    // Write then rename so a crash never leaves a truncated settings file
+    let temp_file = settings_file.with_extension("json.tmp");
+    fs::write(&temp_file, json).map_err(|e| format!("Failed to write settings file: {}", e))?;
+    fs::rename(&temp_file, &settings_file)
+        .map_err(|e| format!("Failed to write settings file: {}", e))
+}
@@ -0,0 +1,290 @@
+use crate::cmd;
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// How long either side of a socket connection waits on the other before
+/// giving up, so a stuck peer can't hang a launch or the listener
+pub(crate) const SOCKET_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Something a launcher invocation asks the running instance to do
+#[derive(Debug, Clone, PartialEq)]
+pub enum Request {
+    Toggle,
+    Show,
+    Hide,
+    /// Show the launcher with the search field filled in
+    Search(String),
+}
+
+/// Parses the arguments a launcher invocation was started with. Accepts
+/// `--toggle`, `--show`, `--hide` and `--search <query>` (or `--search=<query>`).
+pub fn parse_args(args: &[String]) -> Result<Vec<Request>, String> {
+    let mut requests = Vec::new();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let request = match arg.as_str() {
+            "--toggle" => Request::Toggle,
+            "--show" => Request::Show,
+            "--hide" => Request::Hide,
+            "--search" => {
+                let query = args.next().ok_or("--search needs a query")?;
+                Request::Search(query.clone())
+            }
+            _ => match arg.strip_prefix("--search=") {
+                Some(query) => Request::Search(query.to_string()),
+                None => return Err(format!("Unknown argument '{}'", arg)),
+            },
+        };
+        requests.push(request);
+    }
+
+    Ok(requests)
+}
+
+/// Carries out requests in the order they were given
+pub fn handle_args(app: &AppHandle, args: &[String]) -> Result<(), String> {
+    for request in parse_args(args)? {
+        handle(app, request)?;
+    }
+    Ok(())
+}
+
+pub fn handle(app: &AppHandle, request: Request) -> Result<(), String> {
+    match request {
+        Request::Toggle => cmd::toggle_main_window(app),
+        Request::Show => cmd::show_main_window(app.clone()),
+        Request::Hide => match app.get_webview_window("main") {
+            Some(window) => window.hide().map_err(|e| e.to_string()),
+            None => Ok(()),
+        },
+        Request::Search(query) => {
+            cmd::show_main_window(app.clone())?;
+            let _ = app.emit("search-requested", query);
+            Ok(())
+        }
+    }
+}
+
+/// Longest socket path the system accepts, counting the trailing NUL
+#[cfg(target_os = "macos")]
+const SUN_PATH_LEN: usize = 104;
+#[cfg(all(unix, not(target_os = "macos")))]
+const SUN_PATH_LEN: usize = 108;
+
+/// Folder holding the launcher's sockets, readable by the current user only.
+/// It lives in `$XDG_RUNTIME_DIR`, or else the temp folder (a per-user one on
+/// macOS), since the config folder is often too deep for a socket path.
+#[cfg(unix)]
+fn socket_dir() -> Result<PathBuf, String> {
+    if let Some(dir) = std::env::var_os("XDG_RUNTIME_DIR").filter(|dir| !dir.is_empty()) {
+        return Ok(PathBuf::from(dir).join("relaunchpad"));
+    }
+    Ok(std::env::temp_dir().join(format!("relaunchpad-{}", user_id()?)))
+}
+
+/// Path of the socket called `name`. Fails when the path is too long to bind
+/// or the folder belongs to another user, as it could in a shared `/tmp`.
+#[cfg(unix)]
+pub(crate) fn socket_file(name: &str) -> Result<PathBuf, String> {
+    use std::os::unix::fs::MetadataExt;
+
+    let dir = socket_dir()?;
+    if let Ok(metadata) = std::fs::symlink_metadata(&dir) {
+        if metadata.uid() != user_id()? {
+            return Err(format!("{} belongs to another user", dir.display()));
+        }
+    }
+
+    let path = dir.join(name);
+    let len = path.as_os_str().len();
+    if len >= SUN_PATH_LEN {
+        return Err(format!(
+            "Socket path {} is {} bytes, over the limit of {}; set XDG_RUNTIME_DIR or TMPDIR to a shorter folder",
+            path.display(),
+            len,
+            SUN_PATH_LEN - 1
+        ));
+    }
+    Ok(path)
+}
+
+/// The current user's id, taken from the owner of the home directory
+#[cfg(unix)]
+fn user_id() -> Result<u32, String> {
+    use std::os::unix::fs::MetadataExt;
+
+    let home_dir = std::env::var("HOME").map_err(|_| "Could not find home directory")?;
+    std::fs::metadata(home_dir)
+        .map(|metadata| metadata.uid())
+        .map_err(|e| format!("Failed to read home directory: {}", e))
+}
+
+/// Socket the first instance listens on for arguments from later ones
+#[cfg(unix)]
+fn socket_path() -> Result<PathBuf, String> {
+    socket_file("relaunchpad.sock")
+}
+
+/// Hands `args` to an already running instance. Returns false when there is
+/// none, in which case this process should become the running instance.
+#[cfg(unix)]
+pub fn forward(args: &[String]) -> Result<bool, String> {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixStream;
+
+    let Ok(mut stream) = UnixStream::connect(socket_path()?) else {
+        return Ok(false);
+    };
+    stream
+        .set_read_timeout(Some(SOCKET_TIMEOUT))
+        .and_then(|_| stream.set_write_timeout(Some(SOCKET_TIMEOUT)))
+        .map_err(|e| format!("Failed to set socket timeout: {}", e))?;
+
+    let message =
+        serde_json::to_string(args).map_err(|e| format!("Failed to serialize arguments: {}", e))?;
+    writeln!(stream, "{}", message).map_err(|e| format!("Failed to forward arguments: {}", e))?;
+
+    let mut reply = String::new();
+    BufReader::new(stream)
+        .read_line(&mut reply)
+        .map_err(|e| format!("Failed to read reply from running instance: {}", e))?;
+
+    match reply.trim_end() {
+        "ok" => Ok(true),
+        error => Err(error.to_string()),
+    }
+}
+
+#[cfg(not(unix))]
+pub fn forward(_args: &[String]) -> Result<bool, String> {
+    Ok(false)
+}
+
+/// Whether a launcher instance is answering on the socket
+#[cfg(unix)]
+pub fn is_running() -> bool {
+    socket_path().is_ok_and(|path| std::os::unix::net::UnixStream::connect(path).is_ok())
+}
+
+#[cfg(not(unix))]
+pub fn is_running() -> bool {
+    false
+}
+
+/// Accepts arguments forwarded by later invocations and replies with `ok` or
+/// the error they caused, one JSON array of arguments per connection. Each
+/// connection is served on its own thread.
+#[cfg(unix)]
+pub fn listen(app: AppHandle) -> Result<(), String> {
+    use std::thread;
+
+    let listener = bind_private(&socket_path()?)?;
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let app = app.clone();
+                    thread::spawn(move || serve(&app, stream));
+                }
+                Err(e) => eprintln!("Failed to accept forwarded arguments: {}", e),
+            }
+        }
+    });
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn serve(app: &AppHandle, mut stream: std::os::unix::net::UnixStream) {
+    use std::io::{BufRead, BufReader, Write};
+
+    if let Err(e) = stream
+        .set_read_timeout(Some(SOCKET_TIMEOUT))
+        .and_then(|_| stream.set_write_timeout(Some(SOCKET_TIMEOUT)))
+    {
+        eprintln!("Failed to set socket timeout: {}", e);
+        return;
+    }
+
+    let mut message = String::new();
+    let reply = match BufReader::new(&stream).read_line(&mut message) {
+        Ok(_) => serde_json::from_str::<Vec<String>>(&message)
+            .map_err(|e| format!("Invalid forwarded arguments: {}", e))
+            .and_then(|args| handle_args(app, &args)),
+        Err(e) => Err(format!("Failed to read forwarded arguments: {}", e)),
+    };
+
+    let reply = reply.err().unwrap_or_else(|| "ok".to_string());
+    let _ = writeln!(stream, "{}", reply.replace('\n', " "));
+}
+
+#[cfg(not(unix))]
+pub fn listen(_app: AppHandle) -> Result<(), String> {
+    Ok(())
+}
+
+/// Binds a Unix socket only the current user can connect to, replacing a
+/// stale socket file left behind by an instance that didn't shut down cleanly.
+/// The socket is created inside a folder only the user can enter, so it is
+/// never reachable by others, even before its own mode is restricted. A lock
+/// file keeps two starting instances from both deciding the socket is stale
+/// and unlinking each other's.
+#[cfg(unix)]
+pub(crate) fn bind_private(
+    socket_path: &std::path::Path,
+) -> Result<std::os::unix::net::UnixListener, String> {
+    use std::fs;
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+    use std::os::unix::net::{UnixListener, UnixStream};
+
+    if let Some(dir) = socket_path.parent() {
+        fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        // An existing folder keeps its mode, so restrict it as well
+        fs::set_permissions(dir, fs::Permissions::from_mode(0o700))
+            .map_err(|e| format!("Failed to restrict {}: {}", dir.display(), e))?;
+    }
+
+    let lock_path = socket_path.with_extension("lock");
+    let lock = fs::File::create(&lock_path)
+        .map_err(|e| format!("Failed to create {}: {}", lock_path.display(), e))?;
+    lock.lock()
+        .map_err(|e| format!("Failed to lock {}: {}", lock_path.display(), e))?;
+
+    // Nobody answered on the socket, so the file doesn't belong to a live instance
+    if UnixStream::connect(socket_path).is_err() {
+        let _ = fs::remove_file(socket_path);
+    }
+    let listener = UnixListener::bind(socket_path)
+        .map_err(|e| format!("Failed to listen on {}: {}", socket_path.display(), e))?;
+
+    if let Err(e) = fs::set_permissions(socket_path, fs::Permissions::from_mode(0o600)) {
+        let _ = fs::remove_file(socket_path);
+        return Err(format!(
+            "Failed to restrict {}: {}",
+            socket_path.display(),
+            e
+        ));
+    }
+
+    // The lock is released when `lock` is dropped, once the socket is bound
+    Ok(listener)
+}
+
+/// Removes the socket so the next launch doesn't have to probe a stale one
+#[cfg(unix)]
+pub fn cleanup() {
+    if let Ok(socket_path) = socket_path() {
+        let _ = std::fs::remove_file(socket_path);
+    }
+}
+
+#[cfg(not(unix))]
+pub fn cleanup() {}
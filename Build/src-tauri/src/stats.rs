@@ -0,0 +1,479 @@
+use crate::discovery::AppInfo;
+use crate::usage::AppUsage;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::fs;
+
+/// How far back a report looks, counting today
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum StatsRange {
+    Week,
+    Month,
+    Year,
+    #[default]
+    All,
+}
+
+impl StatsRange {
+    /// First day included in the report
+    fn start(self, today: NaiveDate) -> Option<NaiveDate> {
+        let days = match self {
+            StatsRange::Week => 7,
+            StatsRange::Month => 30,
+            StatsRange::Year => 365,
+            StatsRange::All => return None,
+        };
+        Some(today - Duration::days(days - 1))
+    }
+}
+
+/// How per-app launches are bucketed
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupBy {
+    #[default]
+    Day,
+    Week,
+}
+
+impl GroupBy {
+    /// `2026-10-18` for days, `2026-W42` for ISO weeks
+    fn period(self, day: NaiveDate) -> String {
+        match self {
+            GroupBy::Day => day.to_string(),
+            GroupBy::Week => {
+                let week = day.iso_week();
+                format!("{}-W{:02}", week.year(), week.week())
+            }
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    Json,
+    Csv,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct UsageReport {
+    pub range: StatsRange,
+    pub group_by: GroupBy,
+    pub generated_at: String,
+    /// Launches within the range across all apps
+    pub total_launches: u32,
+    /// Apps launched within the range, most launched first
+    pub apps: Vec<AppStats>,
+    /// Launches within the range per app category, most launched first
+    pub categories: Vec<CategoryTotal>,
+    /// Indexed apps with no recorded launch at all
+    pub never_launched: Vec<NeverLaunched>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct AppStats {
+    pub bundle_id: String,
+    /// Missing for apps that are no longer indexed
+    pub name: Option<String>,
+    pub category: Option<String>,
+    /// Launches within the range
+    pub launches: u32,
+    /// Launches ever recorded
+    pub total_launches: u32,
+    pub first_launched: Option<String>,
+    pub last_launched: Option<String>,
+    /// Consecutive days with a launch, ending today or yesterday
+    pub current_streak_days: u32,
+    pub longest_streak_days: u32,
+    pub periods: Vec<PeriodCount>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PeriodCount {
+    pub period: String,
+    pub launches: u32,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct CategoryTotal {
+    /// `uncategorized` for apps without a category
+    pub category: String,
+    pub launches: u32,
+    pub apps: usize,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct NeverLaunched {
+    pub bundle_id: String,
+    pub name: String,
+    /// When the bundle was created on disk, if the filesystem knows
+    pub installed: Option<String>,
+}
+
+/// Builds a report from stored usage. Per-period counts, streaks and range
+/// totals come from the per-day history, so launches recorded before it
+/// existed only show up in `total_launches`.
+pub fn usage_report(
+    usage_data: &[AppUsage],
+    apps: &[AppInfo],
+    range: StatsRange,
+    group_by: GroupBy,
+    today: NaiveDate,
+) -> UsageReport {
+    let apps_by_id: HashMap<&str, &AppInfo> = apps
+        .iter()
+        .map(|app| (app.bundle_id.as_str(), app))
+        .collect();
+    let start = range.start(today);
+
+    let mut app_stats: Vec<AppStats> = usage_data
+        .iter()
+        .map(|usage| {
+            let info = apps_by_id.get(usage.bundle_id.as_str());
+            let days = launch_days(usage);
+
+            let mut periods: BTreeMap<String, u32> = BTreeMap::new();
+            for (day, count) in &days {
+                if start.is_none_or(|start| *day >= start) && *day <= today {
+                    *periods.entry(group_by.period(*day)).or_insert(0) += count;
+                }
+            }
+            let (current_streak_days, longest_streak_days) = streaks(&days, today);
+
+            AppStats {
+                bundle_id: usage.bundle_id.clone(),
+                name: info.map(|app| app.name.clone()),
+                category: info.and_then(|app| app.category.clone()),
+                launches: periods.values().sum(),
+                total_launches: usage.launch_count,
+                first_launched: usage.first_launched.clone(),
+                last_launched: usage.last_launched.clone(),
+                current_streak_days,
+                longest_streak_days,
+                periods: periods
+                    .into_iter()
+                    .map(|(period, launches)| PeriodCount { period, launches })
+                    .collect(),
+            }
+        })
+        .filter(|stats| stats.launches > 0 || range == StatsRange::All)
+        .collect();
+    app_stats.sort_by(|a, b| {
+        b.launches
+            .cmp(&a.launches)
+            .then_with(|| a.bundle_id.cmp(&b.bundle_id))
+    });
+
+    let mut categories: HashMap<String, CategoryTotal> = HashMap::new();
+    for stats in app_stats.iter().filter(|stats| stats.launches > 0) {
+        let category = stats
+            .category
+            .clone()
+            .unwrap_or_else(|| "uncategorized".to_string());
+        let total = categories.entry(category.clone()).or_insert(CategoryTotal {
+            category,
+            launches: 0,
+            apps: 0,
+        });
+        total.launches += stats.launches;
+        total.apps += 1;
+    }
+    let mut categories: Vec<CategoryTotal> = categories.into_values().collect();
+    categories.sort_by(|a, b| {
+        b.launches
+            .cmp(&a.launches)
+            .then_with(|| a.category.cmp(&b.category))
+    });
+
+    let mut never_launched: Vec<NeverLaunched> = apps
+        .iter()
+        .filter(|app| {
+            !usage_data
+                .iter()
+                .any(|usage| usage.bundle_id == app.bundle_id)
+        })
+        .map(|app| NeverLaunched {
+            bundle_id: app.bundle_id.clone(),
+            name: app.name.clone(),
+            installed: installed_time(&app.path).map(|time| time.to_rfc3339()),
+        })
+        .collect();
+    never_launched.sort_by_key(|app| app.name.to_lowercase());
+
+    UsageReport {
+        range,
+        group_by,
+        generated_at: Utc::now().to_rfc3339(),
+        total_launches: app_stats.iter().map(|stats| stats.launches).sum(),
+        apps: app_stats,
+        categories,
+        never_launched,
+    }
+}
+
+/// Days with at least one launch, oldest first
+fn launch_days(usage: &AppUsage) -> Vec<(NaiveDate, u32)> {
+    usage
+        .daily
+        .iter()
+        .filter_map(|(day, count)| Some((day.parse().ok()?, *count)))
+        .filter(|(_, count)| *count > 0)
+        .collect()
+}
+
+/// The current and longest runs of consecutive launch days
+fn streaks(days: &[(NaiveDate, u32)], today: NaiveDate) -> (u32, u32) {
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous: Option<NaiveDate> = None;
+
+    for (day, _) in days {
+        run = match previous {
+            Some(previous) if *day - previous == Duration::days(1) => run + 1,
+            _ => 1,
+        };
+        longest = longest.max(run);
+        previous = Some(*day);
+    }
+
+    // The latest run only counts as current if it hasn't been broken yet
+    let current = match previous {
+        Some(last) if today - last <= Duration::days(1) => run,
+        _ => 0,
+    };
+    (current, longest)
+}
+
+/// When a bundle was installed, going by its creation time where the
+/// filesystem records one and its modification time otherwise
+pub(crate) fn installed_time(path: &str) -> Option<DateTime<Utc>> {
+    let metadata = fs::metadata(path).ok()?;
+    let created = metadata.created().or_else(|_| metadata.modified()).ok()?;
+    Some(DateTime::<Utc>::from(created))
+}
+
+/// Serializes a report for export. CSV has one row per app launched in the
+/// range; per-period counts are only in the JSON export.
+pub fn export_report(report: &UsageReport, format: ExportFormat) -> Result<String, String> {
+    match format {
+        ExportFormat::Json => serde_json::to_string_pretty(report)
+            .map_err(|e| format!("Failed to serialize usage report: {}", e)),
+        ExportFormat::Csv => Ok(report_csv(report)),
+    }
+}
+
+fn report_csv(report: &UsageReport) -> String {
+    let mut csv = String::from(
+        "bundle_id,name,category,launches,total_launches,first_launched,last_launched,\
+         current_streak_days,longest_streak_days\n",
+    );
+
+    for stats in &report.apps {
+        let fields = [
+            stats.bundle_id.clone(),
+            stats.name.clone().unwrap_or_default(),
+            stats.category.clone().unwrap_or_default(),
+            stats.launches.to_string(),
+            stats.total_launches.to_string(),
+            stats.first_launched.clone().unwrap_or_default(),
+            stats.last_launched.clone().unwrap_or_default(),
+            stats.current_streak_days.to_string(),
+            stats.longest_streak_days.to_string(),
+        ];
+        let row: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        let _ = writeln!(csv, "{}", row.join(","));
+    }
+
+    csv
+}
+
+/// Quotes a field when it contains a separator, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(value: &str) -> NaiveDate {
+        value.parse().unwrap()
+    }
+
+    fn days(values: &[&str]) -> Vec<(NaiveDate, u32)> {
+        values.iter().map(|value| (day(value), 1)).collect()
+    }
+
+    fn usage(bundle_id: &str, daily: &[(&str, u32)]) -> AppUsage {
+        AppUsage {
+            bundle_id: bundle_id.to_string(),
+            launch_count: daily.iter().map(|(_, count)| count).sum(),
+            last_launched: None,
+            first_launched: None,
+            daily: daily
+                .iter()
+                .map(|(day, count)| (day.to_string(), *count))
+                .collect(),
+        }
+    }
+
+    fn app(bundle_id: &str, name: &str, category: Option<&str>) -> AppInfo {
+        AppInfo {
+            name: name.to_string(),
+            bundle_id: bundle_id.to_string(),
+            path: format!("/nonexistent/{}.app", bundle_id),
+            icon_path: None,
+            icon_key: None,
+            category: category.map(str::to_string),
+            version: None,
+            alternates: Vec::new(),
+            running: false,
+        }
+    }
+
+    #[test]
+    fn streaks_break_on_gap_days() {
+        let today = day("2026-10-18");
+        assert_eq!(streaks(&[], today), (0, 0));
+        assert_eq!(
+            streaks(
+                &days(&[
+                    "2026-10-01",
+                    "2026-10-02",
+                    "2026-10-03",
+                    "2026-10-05",
+                    "2026-10-06"
+                ]),
+                today
+            ),
+            (0, 3)
+        );
+        assert_eq!(
+            streaks(
+                &days(&["2026-10-14", "2026-10-16", "2026-10-17", "2026-10-18"]),
+                today
+            ),
+            (3, 3)
+        );
+    }
+
+    #[test]
+    fn current_streak_survives_until_today_is_over() {
+        let today = day("2026-10-18");
+        // Nothing launched yet today; yesterday's run still counts
+        assert_eq!(streaks(&days(&["2026-10-16", "2026-10-17"]), today), (2, 2));
+        // A day without launches in between ends it
+        assert_eq!(streaks(&days(&["2026-10-15", "2026-10-16"]), today), (0, 2));
+    }
+
+    #[test]
+    fn groups_launches_by_iso_week() {
+        let usage_data = [usage(
+            "com.example.App",
+            &[
+                ("2026-10-11", 1),
+                ("2026-10-12", 2),
+                ("2026-10-18", 3),
+                ("2026-12-28", 1),
+                ("2027-01-01", 1),
+                ("2027-01-04", 1),
+            ],
+        )];
+
+        let report = usage_report(
+            &usage_data,
+            &[],
+            StatsRange::All,
+            GroupBy::Week,
+            day("2027-01-04"),
+        );
+        let periods: Vec<(&str, u32)> = report.apps[0]
+            .periods
+            .iter()
+            .map(|period| (period.period.as_str(), period.launches))
+            .collect();
+        assert_eq!(
+            periods,
+            vec![
+                ("2026-W41", 1),
+                ("2026-W42", 5),
+                ("2026-W53", 2),
+                ("2027-W01", 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn limits_the_report_to_the_range() {
+        let usage_data = [
+            usage(
+                "com.example.Recent",
+                &[("2026-10-12", 2), ("2026-10-18", 1)],
+            ),
+            usage("com.example.Old", &[("2026-10-01", 4)]),
+            usage("com.example.Broken", &[("yesterday", 1), ("2026-10-17", 0)]),
+        ];
+        let apps = [app("com.example.Recent", "Recent", Some("Productivity"))];
+
+        let report = usage_report(
+            &usage_data,
+            &apps,
+            StatsRange::Week,
+            GroupBy::Day,
+            day("2026-10-18"),
+        );
+        assert_eq!(report.total_launches, 3);
+        assert_eq!(report.apps.len(), 1);
+        assert_eq!(report.apps[0].name.as_deref(), Some("Recent"));
+        assert_eq!(report.categories[0].category, "Productivity");
+
+        let all = usage_report(
+            &usage_data,
+            &apps,
+            StatsRange::All,
+            GroupBy::Day,
+            day("2026-10-18"),
+        );
+        assert_eq!(all.total_launches, 7);
+        assert_eq!(all.apps.len(), 3);
+    }
+
+    #[test]
+    fn quotes_csv_fields_only_when_needed() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+        assert_eq!(csv_field(""), "");
+    }
+
+    #[test]
+    fn writes_one_csv_row_per_app() {
+        let usage_data = [usage("com.example.App", &[("2026-10-18", 2)])];
+        let apps = [app("com.example.App", "Tools, \"Pro\"", None)];
+        let report = usage_report(
+            &usage_data,
+            &apps,
+            StatsRange::All,
+            GroupBy::Day,
+            day("2026-10-18"),
+        );
+
+        let csv = report_csv(&report);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("bundle_id,name,category,launches"));
+        assert_eq!(
+            lines[1],
+            "com.example.App,\"Tools, \"\"Pro\"\"\",,2,2,,,1,1"
+        );
+    }
+}
@@ -0,0 +1,519 @@
+use crate::settings;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Locations owned by the operating system. Bundles in them are never
+/// trashed, even where the filesystem would allow it.
+const PROTECTED_LOCATIONS: &[&str] = &[
+    "/System",
+    "/Library/Apple",
+    "/bin",
+    "/sbin",
+    "/usr",
+    "/lib",
+    "/lib64",
+    "/etc",
+    "/var",
+    "/nix",
+    "/snap",
+];
+
+/// How many trashed apps can be put back; older entries are forgotten but
+/// stay in the trash
+const UNDO_LIMIT: usize = 50;
+
+/// Where a trashed file ended up, enough to put it back
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TrashedItem {
+    pub original_path: String,
+    pub trashed_path: String,
+    /// The `.trashinfo` file written next to it on Linux
+    pub info_path: Option<String>,
+    pub trashed_at: String,
+}
+
+/// A trashed app that can be put back with `TrashHistory::restore`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UndoEntry {
+    pub bundle_id: String,
+    pub name: String,
+    pub item: TrashedItem,
+}
+
+/// Apps moved to the trash by the launcher, newest last. Changes are saved
+/// before they replace the current list.
+pub struct TrashHistory {
+    entries: Mutex<Vec<UndoEntry>>,
+}
+
+impl TrashHistory {
+    /// Loads the undo history, starting empty if it is missing or broken
+    pub fn load() -> Self {
+        let entries = load_history().unwrap_or_else(|e| {
+            eprintln!("Starting without trash undo history: {}", e);
+            Vec::new()
+        });
+
+        TrashHistory {
+            entries: Mutex::new(entries),
+        }
+    }
+
+    pub fn get(&self) -> Vec<UndoEntry> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    pub fn record(&self, entry: UndoEntry) -> Result<(), String> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let mut updated = entries.clone();
+        updated.push(entry);
+        let excess = updated.len().saturating_sub(UNDO_LIMIT);
+        updated.drain(..excess);
+
+        save_history(&updated)?;
+        *entries = updated;
+        Ok(())
+    }
+
+    /// Moves the most recently trashed copy of `bundle_id`, or the most
+    /// recently trashed app of all, back where it was. An entry whose item
+    /// is gone from the trash is dropped along with the error.
+    pub fn restore(&self, bundle_id: Option<&str>) -> Result<UndoEntry, String> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let position = entries
+            .iter()
+            .rposition(|entry| bundle_id.is_none_or(|id| entry.bundle_id == id))
+            .ok_or_else(|| match bundle_id {
+                Some(id) => format!("Nothing to restore for '{}'", id),
+                None => "Nothing to restore".to_string(),
+            })?;
+
+        let mut updated = entries.clone();
+        let entry = updated.remove(position);
+        let restored = put_back(&entry.item);
+
+        // Keep the entry while the item is still in the trash to retry later
+        let in_trash = Path::new(&entry.item.trashed_path)
+            .symlink_metadata()
+            .is_ok();
+        if restored.is_ok() || !in_trash {
+            save_history(&updated)?;
+            *entries = updated;
+        }
+        restored.map(|()| entry)
+    }
+}
+
+/// The canonical location of a bundle about to be trashed, refusing
+/// anything inside a protected system location or that isn't a bundle
+/// directory
+pub fn check_removable(path: &Path) -> Result<PathBuf, String> {
+    let canonical = fs::canonicalize(path)
+        .map_err(|e| format!("Failed to resolve '{}': {}", path.display(), e))?;
+
+    if let Some(location) = PROTECTED_LOCATIONS
+        .iter()
+        .find(|location| canonical.starts_with(location))
+    {
+        return Err(format!(
+            "'{}' is inside the system location {}",
+            canonical.display(),
+            location
+        ));
+    }
+    if !canonical.is_dir()
+        || canonical
+            .parent()
+            .is_none_or(|parent| parent == Path::new("/"))
+    {
+        return Err(format!("'{}' is not an app bundle", canonical.display()));
+    }
+
+    Ok(canonical)
+}
+
+/// Moves a trashed item back to its original path, removing its
+/// `.trashinfo`. Refuses to overwrite something installed there since.
+fn put_back(item: &TrashedItem) -> Result<(), String> {
+    let original = Path::new(&item.original_path);
+    if original.symlink_metadata().is_ok() {
+        return Err(format!(
+            "'{}' already exists; move it away to restore the trashed copy",
+            original.display()
+        ));
+    }
+
+    fs::rename(&item.trashed_path, original).map_err(|e| {
+        format!(
+            "Failed to restore '{}' from the trash: {}",
+            original.display(),
+            e
+        )
+    })?;
+    if let Some(info_path) = &item.info_path {
+        let _ = fs::remove_file(info_path);
+    }
+    Ok(())
+}
+
+fn history_file() -> Result<PathBuf, String> {
+    Ok(settings::config_dir()?.join("trash_history.json"))
+}
+
+fn load_history() -> Result<Vec<UndoEntry>, String> {
+    let history_file = history_file()?;
+
+    if !history_file.exists() {
+        return Ok(Vec::new());
+    }
+
+    let contents = fs::read_to_string(&history_file)
+        .map_err(|e| format!("Failed to read trash history: {}", e))?;
+
+    serde_json::from_str(&contents).map_err(|e| format!("Failed to parse trash history: {}", e))
+}
+
+fn save_history(entries: &[UndoEntry]) -> Result<(), String> {
+    let history_file = history_file()?;
+
+    if let Some(dir) = history_file.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create config directory: {}", e))?;
+    }
+
+    let json = serde_json::to_string_pretty(entries)
+        .map_err(|e| format!("Failed to serialize trash history: {}", e))?;
+
+    // Write then rename so a crash never leaves a truncated history file
+    let temp_file = history_file.with_extension("json.tmp");
+    fs::write(&temp_file, json).map_err(|e| format!("Failed to write trash history: {}", e))?;
+    fs::rename(&temp_file, &history_file)
+        .map_err(|e| format!("Failed to write trash history: {}", e))
+}
+
+/// Moves `path` into the user's Trash, picking a free name like Finder does
+/// (`Name 2.app`) when one with the same name is already there
+#[cfg(target_os = "macos")]
+pub fn move_to_trash(path: &Path) -> Result<TrashedItem, String> {
+    let home_dir = std::env::var("HOME").map_err(|_| "Could not find home directory")?;
+    let trash_dir = Path::new(&home_dir).join(".Trash");
+    fs::create_dir_all(&trash_dir)
+        .map_err(|e| format!("Failed to create trash directory: {}", e))?;
+
+    let file_name = file_name(path)?;
+    let mut attempt = 1;
+    loop {
+        let target = trash_dir.join(numbered_name(&file_name, attempt));
+        attempt += 1;
+        if target.symlink_metadata().is_ok() {
+            continue;
+        }
+
+        fs::rename(path, &target)
+            .map_err(|e| format!("Failed to move '{}' to the trash: {}", path.display(), e))?;
+        return Ok(TrashedItem {
+            original_path: path.to_string_lossy().into_owned(),
+            trashed_path: target.to_string_lossy().into_owned(),
+            info_path: None,
+            trashed_at: chrono::Utc::now().to_rfc3339(),
+        });
+    }
+}
+
+/// Moves `path` into the trash described by the FreeDesktop.org Trash spec:
+/// the home trash when `path` is on the same filesystem, otherwise
+/// `$topdir/.Trash-$uid` on the filesystem it lives on. The `.trashinfo` file
+/// is created first, exclusively, so it also reserves the name in `files/`.
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn move_to_trash(path: &Path) -> Result<TrashedItem, String> {
+    use std::fs::OpenOptions;
+    use std::io::{ErrorKind, Write};
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::fs::MetadataExt;
+
+    let path = std::path::absolute(path)
+        .map_err(|e| format!("Failed to resolve '{}': {}", path.display(), e))?;
+    let device = fs::symlink_metadata(&path)
+        .map_err(|e| format!("Failed to read '{}': {}", path.display(), e))?
+        .dev();
+    let (trash_dir, top_dir) = trash_dir_for(&path, device)?;
+
+    let files_dir = trash_dir.join("files");
+    let info_dir = trash_dir.join("info");
+    for dir in [&files_dir, &info_dir] {
+        create_private_dir(dir)?;
+    }
+
+    // Paths in the top directory trash are relative to the top directory
+    let recorded_path = match &top_dir {
+        Some(top_dir) => path.strip_prefix(top_dir).unwrap_or(&path),
+        None => &path,
+    };
+    let info = format!(
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        percent_encode(recorded_path.as_os_str().as_bytes()),
+        chrono::Local::now().format("%Y-%m-%dT%H:%M:%S")
+    );
+
+    let file_name = file_name(&path)?;
+    let mut attempt = 1;
+    loop {
+        let name = numbered_name(&file_name, attempt);
+        attempt += 1;
+        let info_path = info_dir.join(format!("{}.trashinfo", name));
+        let target = files_dir.join(&name);
+
+        let mut info_file = match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&info_path)
+        {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(format!("Failed to write trash info: {}", e)),
+        };
+        if target.symlink_metadata().is_ok() {
+            // Left over without its info file; keep it and pick another name
+            let _ = fs::remove_file(&info_path);
+            continue;
+        }
+
+        let moved = info_file
+            .write_all(info.as_bytes())
+            .map_err(|e| format!("Failed to write trash info: {}", e))
+            .and_then(|()| {
+                fs::rename(&path, &target)
+                    .map_err(|e| format!("Failed to move '{}' to the trash: {}", path.display(), e))
+            });
+        if let Err(e) = moved {
+            let _ = fs::remove_file(&info_path);
+            return Err(e);
+        }
+
+        return Ok(TrashedItem {
+            original_path: path.to_string_lossy().into_owned(),
+            trashed_path: target.to_string_lossy().into_owned(),
+            info_path: Some(info_path.to_string_lossy().into_owned()),
+            trashed_at: chrono::Utc::now().to_rfc3339(),
+        });
+    }
+}
+
+#[cfg(not(unix))]
+pub fn move_to_trash(path: &Path) -> Result<TrashedItem, String> {
+    Err(format!(
+        "Moving '{}' to the trash is not supported on this platform",
+        path.display()
+    ))
+}
+
+/// The trash directory for a file on `device`, and the top directory it
+/// belongs to when that isn't the home trash
+#[cfg(all(unix, not(target_os = "macos")))]
+fn trash_dir_for(path: &Path, device: u64) -> Result<(PathBuf, Option<PathBuf>), String> {
+    use std::os::unix::fs::MetadataExt;
+
+    let home_dir = std::env::var("HOME").map_err(|_| "Could not find home directory")?;
+    let data_home = std::env::var("XDG_DATA_HOME")
+        .ok()
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| Path::new(&home_dir).join(".local/share"));
+    let home_trash = data_home.join("Trash");
+
+    // The home trash's filesystem is where its nearest existing ancestor is
+    let home_device = home_trash
+        .ancestors()
+        .find_map(|dir| fs::metadata(dir).ok())
+        .map(|metadata| metadata.dev());
+    if home_device == Some(device) {
+        return Ok((home_trash, None));
+    }
+
+    // Walk up to the mount point: the last ancestor on the same device
+    let mut top_dir = path;
+    while let Some(parent) = top_dir.parent() {
+        match fs::metadata(parent) {
+            Ok(metadata) if metadata.dev() == device => top_dir = parent,
+            _ => break,
+        }
+    }
+
+    let uid = fs::metadata(&home_dir)
+        .map_err(|e| format!("Failed to read home directory: {}", e))?
+        .uid();
+    Ok((
+        top_dir.join(format!(".Trash-{}", uid)),
+        Some(top_dir.to_path_buf()),
+    ))
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn create_private_dir(dir: &Path) -> Result<(), String> {
+    use std::os::unix::fs::DirBuilderExt;
+
+    fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(dir)
+        .map_err(|e| format!("Failed to create trash directory: {}", e))
+}
+
+/// Escapes everything but unreserved characters and `/`, as the spec asks
+#[cfg(all(unix, not(target_os = "macos")))]
+fn percent_encode(value: &[u8]) -> String {
+    let mut encoded = String::new();
+    for &byte in value {
+        if byte.is_ascii_alphanumeric() || b"-._~/".contains(&byte) {
+            encoded.push(char::from(byte));
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+#[cfg(unix)]
+fn file_name(path: &Path) -> Result<String, String> {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .ok_or_else(|| format!("'{}' has no file name", path.display()))
+}
+
+/// `Name.app` for the first attempt, then `Name 2.app`, `Name 3.app`, ...
+#[cfg(unix)]
+fn numbered_name(file_name: &str, attempt: u32) -> String {
+    if attempt == 1 {
+        return file_name.to_string();
+    }
+
+    match file_name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => {
+            format!("{} {}.{}", stem, attempt, extension)
+        }
+        _ => format!("{} {}", file_name, attempt),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh directory under the system temp dir, removed when dropped
+    #[cfg(all(unix, not(target_os = "macos")))]
+    struct TempDir(PathBuf);
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "relaunchpad-trash-{}-{}",
+                std::process::id(),
+                name
+            ));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            TempDir(path)
+        }
+
+        fn bundle(&self, relative: &str) -> PathBuf {
+            let path = self.0.join(relative);
+            fs::create_dir_all(path.join("Contents")).unwrap();
+            fs::write(path.join("Contents/Info.plist"), "").unwrap();
+            path
+        }
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    fn percent_decode(value: &str) -> Vec<u8> {
+        let mut decoded = Vec::new();
+        let mut bytes = value.bytes();
+        while let Some(byte) = bytes.next() {
+            if byte == b'%' {
+                let hex: String = bytes.by_ref().take(2).map(char::from).collect();
+                decoded.push(u8::from_str_radix(&hex, 16).unwrap());
+            } else {
+                decoded.push(byte);
+            }
+        }
+        decoded
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn numbers_names_before_the_extension() {
+        assert_eq!(numbered_name("Name.app", 1), "Name.app");
+        assert_eq!(numbered_name("Name.app", 2), "Name 2.app");
+        assert_eq!(numbered_name("archive.tar.gz", 3), "archive.tar 3.gz");
+        assert_eq!(numbered_name("README", 2), "README 2");
+        assert_eq!(numbered_name(".hidden", 2), ".hidden 2");
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[test]
+    fn percent_encodes_all_but_unreserved_characters() {
+        assert_eq!(
+            percent_encode("/opt/My App/ü%#.app".as_bytes()),
+            "/opt/My%20App/%C3%BC%25%23.app"
+        );
+        assert_eq!(percent_encode(b"/a-b_c.d~e/F9"), "/a-b_c.d~e/F9");
+        assert_eq!(
+            percent_decode("/opt/My%20App/%C3%BC%25%23.app"),
+            "/opt/My App/ü%#.app".as_bytes()
+        );
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[test]
+    fn trash_info_round_trips_to_the_original_path() {
+        let dir = TempDir::new("round-trip");
+        // Only this test points the home trash somewhere else
+        std::env::set_var("XDG_DATA_HOME", dir.0.join("data"));
+        let first = dir.bundle("apps/My App.app");
+
+        let item = move_to_trash(&first).unwrap();
+        assert!(!first.exists());
+        assert_eq!(
+            Path::new(&item.trashed_path),
+            dir.0.join("data/Trash/files/My App.app")
+        );
+        assert!(Path::new(&item.trashed_path)
+            .join("Contents/Info.plist")
+            .exists());
+
+        let info_path = item.info_path.clone().unwrap();
+        let info = fs::read_to_string(&info_path).unwrap();
+        let mut lines = info.lines();
+        assert_eq!(lines.next(), Some("[Trash Info]"));
+        let recorded = lines.next().unwrap().strip_prefix("Path=").unwrap();
+        assert_eq!(
+            percent_decode(recorded),
+            first.as_os_str().as_encoded_bytes()
+        );
+        let deleted = lines.next().unwrap().strip_prefix("DeletionDate=").unwrap();
+        assert!(chrono::NaiveDateTime::parse_from_str(deleted, "%Y-%m-%dT%H:%M:%S").is_ok());
+
+        // A second app with the same name gets a numbered slot
+        let second = dir.bundle("apps/My App.app");
+        let second_item = move_to_trash(&second).unwrap();
+        assert!(second_item.trashed_path.ends_with("files/My App 2.app"));
+
+        // Putting one back refuses to overwrite the other once it is restored
+        put_back(&item).unwrap();
+        assert!(first.join("Contents/Info.plist").exists());
+        assert!(!Path::new(&info_path).exists());
+        assert!(put_back(&second_item).is_err());
+        assert!(Path::new(&second_item.trashed_path).exists());
+    }
+}
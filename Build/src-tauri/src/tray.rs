@@ -0,0 +1,164 @@
+use crate::cmd;
+use crate::favorites::FavoritesStore;
+use crate::index::AppIndex;
+use crate::settings::{self, SettingsStore};
+use crate::single_instance::{self, Request};
+use crate::usage;
+use tauri::menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Listener, Manager};
+
+const TRAY_ID: &str = "main";
+
+/// How many apps each of the app submenus lists
+const MENU_APP_LIMIT: usize = 8;
+
+/// Menu item ids for apps carry this prefix before the bundle id
+const APP_ITEM_PREFIX: &str = "app:";
+
+/// Adds the tray icon and keeps its app lists in step with the index and
+/// usage data
+pub fn create(app: &AppHandle) -> tauri::Result<()> {
+    let mut tray = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("ReLaunchpad")
+        .menu(&build_menu(app)?)
+        .on_menu_event(handle_menu_event);
+    if let Some(icon) = app.default_window_icon() {
+        tray = tray.icon(icon.clone());
+    }
+    tray.build(app)?;
+
+    for event in ["apps-changed", "usage-changed", "favorites-changed"] {
+        let handle = app.clone();
+        app.listen(event, move |_| refresh(&handle));
+    }
+
+    Ok(())
+}
+
+/// Rebuilds the menu from the current index and usage data
+pub fn refresh(app: &AppHandle) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+
+    if let Err(e) = build_menu(app).and_then(|menu| tray.set_menu(Some(menu))) {
+        eprintln!("Failed to rebuild tray menu: {}", e);
+    }
+}
+
+fn build_menu(app: &AppHandle) -> tauri::Result<Menu<tauri::Wry>> {
+    let settings = app.state::<SettingsStore>().get();
+    let usage_data = usage::load_app_usage(&settings.usage_file_path()).unwrap_or_else(|e| {
+        eprintln!("Tray menu shows no usage: {}", e);
+        Vec::new()
+    });
+
+    let favorites = app_submenu(app, "Favorites", app.state::<FavoritesStore>().get())?;
+    let frequent = app_submenu(
+        app,
+        "Frequent",
+        usage::most_launched(usage_data.clone())
+            .into_iter()
+            .map(|usage| usage.bundle_id)
+            .collect(),
+    )?;
+    let recent = app_submenu(
+        app,
+        "Recent",
+        usage::recently_launched(usage_data)
+            .into_iter()
+            .map(|usage| usage.bundle_id)
+            .collect(),
+    )?;
+
+    Menu::with_items(
+        app,
+        &[
+            &MenuItem::with_id(app, "show", "Show ReLaunchpad", true, None::<&str>)?,
+            &PredefinedMenuItem::separator(app)?,
+            &favorites,
+            &frequent,
+            &recent,
+            &PredefinedMenuItem::separator(app)?,
+            &MenuItem::with_id(app, "rescan", "Rescan Apps", true, None::<&str>)?,
+            &MenuItem::with_id(app, "settings", "Settings…", true, None::<&str>)?,
+            &PredefinedMenuItem::separator(app)?,
+            &MenuItem::with_id(app, "quit", "Quit ReLaunchpad", true, None::<&str>)?,
+        ],
+    )
+}
+
+/// A submenu with one item per indexed app in `bundle_ids`, in order
+fn app_submenu(
+    app: &AppHandle,
+    title: &str,
+    bundle_ids: Vec<String>,
+) -> tauri::Result<Submenu<tauri::Wry>> {
+    let index = app.state::<AppIndex>();
+    let submenu = Submenu::new(app, title, true)?;
+
+    let apps = bundle_ids
+        .iter()
+        .filter_map(|bundle_id| index.get(bundle_id))
+        .take(MENU_APP_LIMIT);
+    for info in apps {
+        // `&` marks a mnemonic in menu labels
+        let label = info.name.replace('&', "&&");
+        let id = format!("{}{}", APP_ITEM_PREFIX, info.bundle_id);
+        submenu.append(&MenuItem::with_id(app, id, label, true, None::<&str>)?)?;
+    }
+
+    if submenu.items()?.is_empty() {
+        submenu.set_enabled(false)?;
+    }
+    Ok(submenu)
+}
+
+fn handle_menu_event(app: &AppHandle, event: MenuEvent) {
+    let result = match event.id().as_ref() {
+        "show" => single_instance::handle(app, Request::Show),
+        "rescan" => {
+            cmd::spawn_rescan(app.clone());
+            Ok(())
+        }
+        "settings" => open_settings_file(app),
+        "quit" => {
+            app.exit(0);
+            Ok(())
+        }
+        id => match id.strip_prefix(APP_ITEM_PREFIX) {
+            Some(bundle_id) => {
+                launch(app.clone(), bundle_id.to_string());
+                Ok(())
+            }
+            None => Ok(()),
+        },
+    };
+
+    if let Err(e) = result {
+        eprintln!("Tray action failed: {}", e);
+    }
+}
+
+/// Launches an app the way picking it in the launcher does
+fn launch(app: AppHandle, bundle_id: String) {
+    tauri::async_runtime::spawn_blocking(move || {
+        if let Err(e) = cmd::open_indexed(&app, &bundle_id, None, true) {
+            eprintln!("Failed to launch from tray: {}", e);
+        }
+    });
+}
+
+/// Opens the settings file in the default editor, writing the settings in
+/// effect first if it doesn't exist yet. Edits are picked up by the
+/// settings watcher.
+fn open_settings_file(app: &AppHandle) -> Result<(), String> {
+    let settings_file = settings::settings_file()?;
+    if !settings_file.exists() {
+        app.state::<SettingsStore>().get().save()?;
+    }
+
+    tauri_plugin_opener::open_path(&settings_file, None::<&str>)
+        .map_err(|e| format!("Failed to open {}: {}", settings_file.display(), e))
+}
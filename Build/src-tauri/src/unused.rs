@@ -0,0 +1,258 @@
+use crate::discovery::AppInfo;
+use crate::settings;
+use crate::stats;
+use crate::trash::UndoEntry;
+use crate::usage::AppUsage;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// An app nobody has launched for a while
+#[derive(Serialize, Debug, Clone)]
+pub struct UnusedApp {
+    pub bundle_id: String,
+    pub name: String,
+    pub path: String,
+    /// Disk space used by the bundle, if it could be measured
+    pub size_bytes: Option<u64>,
+    pub last_launched: Option<String>,
+    pub installed: Option<String>,
+    /// Days since the last launch, or since install for apps never launched.
+    /// Missing when neither is known.
+    pub days_unused: Option<i64>,
+    /// False for apps with no launch history at all
+    pub tracked: bool,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct UnusedReport {
+    pub days: u32,
+    pub generated_at: String,
+    /// Combined size of the listed bundles
+    pub total_bytes: u64,
+    /// Largest bundles first
+    pub apps: Vec<UnusedApp>,
+}
+
+/// What `trash_unused_apps` did with each requested app
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct CleanupReport {
+    pub trashed: Vec<UndoEntry>,
+    pub failed: Vec<CleanupFailure>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct CleanupFailure {
+    pub bundle_id: String,
+    pub message: String,
+}
+
+/// Apps among `apps` not launched in the last `days` days. Apps with no
+/// launch history count from when they were installed, so something
+/// installed yesterday isn't reported; if that's unknown too they are
+/// always reported. Sizes are left for `with_sizes` since measuring is slow.
+pub fn unused_apps(
+    apps: &[AppInfo],
+    usage_data: &[AppUsage],
+    days: u32,
+    now: DateTime<Utc>,
+) -> Vec<UnusedApp> {
+    apps.iter()
+        .filter_map(|app| {
+            let usage = usage_data
+                .iter()
+                .find(|usage| usage.bundle_id == app.bundle_id);
+            let last_launched = usage.and_then(|usage| usage.last_launched.clone());
+            let installed = stats::installed_time(&app.path);
+
+            let since = match &last_launched {
+                Some(last_launched) => DateTime::parse_from_rfc3339(last_launched)
+                    .ok()
+                    .map(|time| time.with_timezone(&Utc)),
+                None => installed,
+            };
+            let days_unused = since.map(|since| (now - since).num_days());
+            if days_unused.is_some_and(|days_unused| days_unused < i64::from(days)) {
+                return None;
+            }
+
+            Some(UnusedApp {
+                bundle_id: app.bundle_id.clone(),
+                name: app.name.clone(),
+                path: app.path.clone(),
+                size_bytes: None,
+                last_launched,
+                installed: installed.map(|time| time.to_rfc3339()),
+                days_unused,
+                tracked: usage.is_some(),
+            })
+        })
+        .collect()
+}
+
+/// Measures each bundle and orders the report largest first; bundles that
+/// couldn't be measured go last
+pub fn with_sizes(mut apps: Vec<UnusedApp>, sizes: &BundleSizes, days: u32) -> UnusedReport {
+    for app in &mut apps {
+        app.size_bytes = sizes.size_of(Path::new(&app.path));
+    }
+    apps.sort_by(|a, b| {
+        b.size_bytes
+            .cmp(&a.size_bytes)
+            .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+    });
+
+    UnusedReport {
+        days,
+        generated_at: Utc::now().to_rfc3339(),
+        total_bytes: apps.iter().filter_map(|app| app.size_bytes).sum(),
+        apps,
+    }
+}
+
+/// A measured bundle size and what the bundle looked like when measured
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct CachedSize {
+    stamp: String,
+    bytes: u64,
+}
+
+/// Bundle sizes keyed by path, kept across runs since walking every bundle
+/// takes a while. An entry is measured again once its bundle changes.
+pub struct BundleSizes {
+    entries: Mutex<HashMap<String, CachedSize>>,
+}
+
+impl BundleSizes {
+    /// Loads the size cache, starting empty if it is missing or broken
+    pub fn load() -> Self {
+        let entries = load_cache().unwrap_or_else(|e| {
+            eprintln!("Starting without cached bundle sizes: {}", e);
+            HashMap::new()
+        });
+
+        BundleSizes {
+            entries: Mutex::new(entries),
+        }
+    }
+
+    /// Disk space used by the bundle at `path`, from the cache unless the
+    /// bundle changed since it was measured
+    pub fn size_of(&self, path: &Path) -> Option<u64> {
+        let key = path.to_string_lossy().into_owned();
+        let stamp = bundle_stamp(path)?;
+
+        if let Some(cached) = self
+            .entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&key)
+        {
+            if cached.stamp == stamp {
+                return Some(cached.bytes);
+            }
+        }
+
+        // Measured without holding the lock, since large bundles take a while
+        let bytes = disk_usage(path);
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(key, CachedSize { stamp, bytes });
+        Some(bytes)
+    }
+
+    /// Writes the cache, dropping bundles that no longer exist
+    pub fn save(&self) -> Result<(), String> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.retain(|path, _| Path::new(path).exists());
+        save_cache(&entries)
+    }
+}
+
+/// Changes when the bundle is replaced or updated in place: its own
+/// modification time together with that of its `Info.plist`
+fn bundle_stamp(path: &Path) -> Option<String> {
+    let modified = |path: &Path| {
+        fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .map(|time| DateTime::<Utc>::from(time).to_rfc3339())
+    };
+
+    let bundle = modified(path)?;
+    let info = modified(&path.join("Contents/Info.plist")).unwrap_or_default();
+    Some(format!("{} {}", bundle, info))
+}
+
+/// Space used by everything below `path`. Symlinks count as themselves and
+/// are not followed; unreadable entries are skipped.
+fn disk_usage(path: &Path) -> u64 {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return 0;
+    };
+
+    let own = allocated_bytes(&metadata);
+    if !metadata.is_dir() {
+        return own;
+    }
+
+    let children = fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| disk_usage(&entry.path()))
+                .sum()
+        })
+        .unwrap_or(0);
+    own + children
+}
+
+/// Blocks actually allocated, so sparse and compressed files aren't overcounted
+#[cfg(unix)]
+fn allocated_bytes(metadata: &fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    metadata.blocks() * 512
+}
+
+#[cfg(not(unix))]
+fn allocated_bytes(metadata: &fs::Metadata) -> u64 {
+    metadata.len()
+}
+
+fn cache_file() -> Result<PathBuf, String> {
+    Ok(settings::config_dir()?.join("bundle_sizes.json"))
+}
+
+fn load_cache() -> Result<HashMap<String, CachedSize>, String> {
+    let cache_file = cache_file()?;
+
+    if !cache_file.exists() {
+        return Ok(HashMap::new());
+    }
+
+    let contents = fs::read_to_string(&cache_file)
+        .map_err(|e| format!("Failed to read bundle size cache: {}", e))?;
+
+    serde_json::from_str(&contents).map_err(|e| format!("Failed to parse bundle size cache: {}", e))
+}
+
+fn save_cache(entries: &HashMap<String, CachedSize>) -> Result<(), String> {
+    let cache_file = cache_file()?;
+
+    if let Some(dir) = cache_file.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create config directory: {}", e))?;
+    }
+
+    let json = serde_json::to_string(entries)
+        .map_err(|e| format!("Failed to serialize bundle size cache: {}", e))?;
+
+    // Write then rename so a crash never leaves a truncated cache file
+    let temp_file = cache_file.with_extension("json.tmp");
+    fs::write(&temp_file, json).map_err(|e| format!("Failed to write bundle size cache: {}", e))?;
+    fs::rename(&temp_file, &cache_file)
+        .map_err(|e| format!("Failed to write bundle size cache: {}", e))
+}
@@ -0,0 +1,197 @@
+use crate::settings::Settings;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Serializes writers in this process so a launch recorded while history is
+/// being purged can't resurrect the old file
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AppUsage {
+    pub bundle_id: String,
+    pub launch_count: u32,
+    pub last_launched: Option<String>,
+    /// Missing for apps whose history predates per-day counts
+    #[serde(default)]
+    pub first_launched: Option<String>,
+    /// Launches per local calendar day, keyed `YYYY-MM-DD`
+    #[serde(default)]
+    pub daily: BTreeMap<String, u32>,
+}
+
+/// What may be recorded in the usage file
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct UsageTracking {
+    /// Record nothing until tracking is resumed
+    pub paused: bool,
+    /// Bundle ids that are never recorded
+    pub excluded: Vec<String>,
+}
+
+impl UsageTracking {
+    pub fn allows(&self, bundle_id: &str) -> bool {
+        !self.paused && !self.is_excluded(bundle_id)
+    }
+
+    pub fn is_excluded(&self, bundle_id: &str) -> bool {
+        self.excluded.iter().any(|id| id == bundle_id)
+    }
+}
+
+/// Reads the launch counts; a missing file means nothing was launched yet
+pub fn load_app_usage(usage_file: &Path) -> Result<Vec<AppUsage>, String> {
+    if !usage_file.exists() {
+        return Ok(Vec::new());
+    }
+
+    let contents =
+        fs::read_to_string(usage_file).map_err(|e| format!("Failed to read usage file: {}", e))?;
+
+    serde_json::from_str(&contents).map_err(|e| format!("Failed to parse usage data: {}", e))
+}
+
+/// Writes the usage file so only the current user can read it
+pub fn save_app_usage(usage_file: &Path, usage_data: &[AppUsage]) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(usage_data)
+        .map_err(|e| format!("Failed to serialize usage data: {}", e))?;
+
+    // Write then rename so a crash never leaves a truncated usage file
+    let temp_file = usage_file.with_extension("json.tmp");
+    fs::write(&temp_file, json).map_err(|e| format!("Failed to write usage file: {}", e))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&temp_file, fs::Permissions::from_mode(0o600))
+            .map_err(|e| format!("Failed to restrict usage file: {}", e))?;
+    }
+
+    fs::rename(&temp_file, usage_file).map_err(|e| format!("Failed to write usage file: {}", e))
+}
+
+/// Counts a launch of `bundle_id` unless tracking is paused or the app is
+/// excluded. Returns whether the launch was recorded. An unreadable usage
+/// file starts over rather than blocking the launch from being counted.
+pub fn record_launch(settings: &Settings, bundle_id: &str) -> Result<bool, String> {
+    if !settings.usage_tracking.allows(bundle_id) {
+        return Ok(false);
+    }
+
+    let _guard = WRITE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let usage_file = settings.usage_file_path();
+    let mut usage_data = load_app_usage(&usage_file).unwrap_or_default();
+    let now = chrono::Utc::now().to_rfc3339();
+    let today = chrono::Local::now().date_naive().to_string();
+
+    // Find existing entry or create new one
+    let index = match usage_data.iter().position(|u| u.bundle_id == bundle_id) {
+        Some(index) => index,
+        None => {
+            usage_data.push(AppUsage {
+                bundle_id: bundle_id.to_string(),
+                launch_count: 0,
+                last_launched: None,
+                first_launched: Some(now.clone()),
+                daily: BTreeMap::new(),
+            });
+            usage_data.len() - 1
+        }
+    };
+
+    let usage = &mut usage_data[index];
+    usage.launch_count += 1;
+    usage.last_launched = Some(now);
+    *usage.daily.entry(today).or_insert(0) += 1;
+
+    save_app_usage(&usage_file, &usage_data)?;
+    Ok(true)
+}
+
+/// Replaces all recorded usage, e.g. with imported history. Entries for
+/// apps excluded from tracking are dropped rather than written.
+pub fn replace_all(settings: &Settings, usage_data: &[AppUsage]) -> Result<(), String> {
+    let _guard = WRITE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let allowed: Vec<AppUsage> = usage_data
+        .iter()
+        .filter(|usage| !settings.usage_tracking.is_excluded(&usage.bundle_id))
+        .cloned()
+        .collect();
+
+    save_app_usage(&settings.usage_file_path(), &allowed)
+}
+
+/// Adds the launches in `incoming` to `usage_data`. Total and per-day counts
+/// are summed and the first and last launch widened to cover both, so merging
+/// the same history twice counts it twice.
+pub fn merge(usage_data: &mut Vec<AppUsage>, incoming: &[AppUsage]) {
+    for other in incoming {
+        let Some(usage) = usage_data
+            .iter_mut()
+            .find(|usage| usage.bundle_id == other.bundle_id)
+        else {
+            usage_data.push(other.clone());
+            continue;
+        };
+
+        usage.launch_count = usage.launch_count.saturating_add(other.launch_count);
+        // RFC 3339 timestamps in UTC compare chronologically as strings
+        usage.first_launched = match (usage.first_launched.take(), &other.first_launched) {
+            (Some(ours), Some(theirs)) => Some(ours.min(theirs.clone())),
+            (ours, theirs) => ours.or_else(|| theirs.clone()),
+        };
+        usage.last_launched = match (usage.last_launched.take(), &other.last_launched) {
+            (Some(ours), Some(theirs)) => Some(ours.max(theirs.clone())),
+            (ours, theirs) => ours.or_else(|| theirs.clone()),
+        };
+        for (day, count) in &other.daily {
+            let total = usage.daily.entry(day.clone()).or_insert(0);
+            *total = total.saturating_add(*count);
+        }
+    }
+}
+
+/// Deletes everything recorded about `bundle_id`. Returns whether there was
+/// anything to delete.
+pub fn forget_app(usage_file: &Path, bundle_id: &str) -> Result<bool, String> {
+    let _guard = WRITE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut usage_data = load_app_usage(usage_file)?;
+
+    let before = usage_data.len();
+    usage_data.retain(|usage| usage.bundle_id != bundle_id);
+    if usage_data.len() == before {
+        return Ok(false);
+    }
+
+    save_app_usage(usage_file, &usage_data)?;
+    Ok(true)
+}
+
+/// Deletes all launch history by removing the usage file. One that doesn't
+/// parse as usage data is left alone, so a misconfigured path can't be used
+/// to destroy other files.
+pub fn purge(usage_file: &Path) -> Result<(), String> {
+    let _guard = WRITE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    if !usage_file.exists() {
+        return Ok(());
+    }
+    load_app_usage(usage_file)?;
+
+    fs::remove_file(usage_file).map_err(|e| format!("Failed to delete usage file: {}", e))
+}
+
+/// Usage entries ordered by when they were last launched, most recent first
+pub fn recently_launched(mut usage_data: Vec<AppUsage>) -> Vec<AppUsage> {
+    // RFC 3339 timestamps in UTC sort chronologically as strings
+    usage_data.sort_by(|a, b| b.last_launched.cmp(&a.last_launched));
+    usage_data
+}
+
+/// Usage entries ordered by launch count, most launched first
+pub fn most_launched(mut usage_data: Vec<AppUsage>) -> Vec<AppUsage> {
+    usage_data.sort_by_key(|usage| std::cmp::Reverse(usage.launch_count));
+    usage_data
+}